@@ -1,10 +1,19 @@
 fn main() {
     let mut config = prost_build::Config::new();
     // Generate into OUT_DIR (default). We'll `include!` it from src/main.rs
+    // Ordered maps keep the encoded feed deterministic.
+    config.btree_map(["."]);
     config
-        .compile_protos(&["proto/linkleaf/v1/feed.proto"], &["proto"])
+        .compile_protos(
+            &[
+                "proto/linkleaf/v1/feed.proto",
+                "proto/linkleaf/v1/search.proto",
+            ],
+            &["proto"],
+        )
         .expect("failed to compile protos");
 
     // Re-run build if the .proto changes
     println!("cargo:rerun-if-changed=proto/linkleaf/v1/feed.proto");
+    println!("cargo:rerun-if-changed=proto/linkleaf/v1/search.proto");
 }
@@ -1,38 +1,8 @@
 use anyhow::Result;
 use tempfile::tempdir;
 
-use linkleaf_core::{add, linkleaf_proto::DateTime, list};
-use time::Month;
-use time::{OffsetDateTime, UtcOffset};
-
-fn from_month(value: Month) -> i32 {
-    match value {
-        Month::January => 1,
-        Month::February => 2,
-        Month::March => 3,
-        Month::April => 4,
-        Month::May => 5,
-        Month::June => 6,
-        Month::July => 7,
-        Month::August => 8,
-        Month::September => 9,
-        Month::October => 10,
-        Month::November => 11,
-        Month::December => 12,
-    }
-}
-
-fn to_proto_datetime(offsetdatetime: &OffsetDateTime) -> Result<DateTime> {
-    Ok(DateTime {
-        year: offsetdatetime.year(),
-        month: from_month(offsetdatetime.month()),
-        day: offsetdatetime.day().try_into()?,
-        hours: offsetdatetime.hour().try_into()?,
-        minutes: offsetdatetime.minute().try_into()?,
-        seconds: offsetdatetime.second().try_into()?,
-        nanos: offsetdatetime.nanosecond().try_into()?,
-    })
-}
+use linkleaf_core::{Query, add, linkleaf_proto::DateTime, list};
+use time::Date;
 
 fn main() -> Result<()> {
     let dir = tempdir()?;
@@ -42,42 +12,41 @@ fn main() -> Result<()> {
     let _ = add(
         file.clone(),
         "A",
-        "https://a/".into(),
+        "https://a/",
         None,
-        Some("rust, async".into()),
+        Some("rust, async"),
         None,
         None,
     )?;
     let _ = add(
         file.clone(),
         "B",
-        "https://b/".into(),
+        "https://b/",
         None,
-        Some("tokio".into()),
+        Some("tokio"),
         None,
         None,
     )?;
     let _ = add(
         file.clone(),
         "C",
-        "https://c/".into(),
+        "https://c/",
         None,
-        Some("db, rust".into()),
+        Some("db, rust"),
         None,
         None,
     )?;
 
     // Filter by tag (case-insensitive, any-of)
-    let rust_only = list(&file, Some(vec!["RUST".into()]), None)?;
+    let rust_only = list(&file, Query::any_tag(["RUST"]))?;
     println!("rust_only: {}", rust_only.links.len());
     for l in &rust_only.links {
         println!("- {}", l.title);
     }
 
     // Filter by date
-    let today = OffsetDateTime::now_utc().to_offset(UtcOffset::current_local_offset()?);
-    let today = to_proto_datetime(&today)?;
-    let today_only = list(&file, None, Some(today))?;
+    let today = Date::try_from(DateTime::now_local()?)?;
+    let today_only = list(&file, Query::on(today))?;
     println!("today_only: {}", today_only.links.len());
 
     Ok(())
@@ -10,14 +10,14 @@ fn main() -> Result<()> {
     let _a = add(
         file.clone(),
         "Tokio - Asynchronous Rust",
-        "https://tokio.rs/".into(),
+        "https://tokio.rs/",
         Some(Summary::new("A runtime for reliable async apps")),
-        Some("rust, async, tokio".into()),
+        Some("rust, async, tokio"),
         Some(Via::new("website")),
         None, // generate id
     )?;
 
-    let feed = list(&file, None, None)?;
+    let feed = list(&file, None)?;
 
     let rss_feed = feed_to_rss_xml(&feed, "", "")?;
 
@@ -19,15 +19,15 @@ fn main() -> Result<()> {
     let _a = add(
         file.clone(),
         "Tokio - Asynchronous Rust",
-        "https://tokio.rs/".into(),
+        "https://tokio.rs/",
         Some(Summary::new("A runtime for reliable async apps")),
-        Some("rust, async, tokio".into()),
+        Some("rust, async, tokio"),
         Some(Via::new("website")),
         None, // generate id
     )?;
 
     // list everything
-    let feed = list(&file, None, None)?;
+    let feed = list(&file, None)?;
     println!("feed version: {}", feed.version);
     println!("links: {}", feed.links.len());
     for (i, l) in feed.links.iter().enumerate() {
@@ -11,9 +11,9 @@ fn main() -> Result<()> {
     let first = add(
         file.clone(),
         "First",
-        "https://one/".into(),
+        "https://one/",
         None,
-        Some("alpha".into()),
+        Some("alpha"),
         None,
         None,
     )?;
@@ -22,16 +22,16 @@ fn main() -> Result<()> {
     let updated = add(
         file.clone(),
         "First (updated)",
-        "https://one-new/".into(),
+        "https://one-new/",
         Some(Summary::new("note")),
-        Some("rust,updated".into()),
+        Some("rust,updated"),
         Some(Via::new("hn")),
         Some(Uuid::parse_str(&first.id)?),
     )?;
 
     assert_eq!(updated.id, first.id, "id stays the same on upsert");
 
-    let feed = list(&file, None, None)?;
+    let feed = list(&file, None)?;
     println!("links: {}", feed.links.len());
     println!("front item: {} [{}]", feed.links[0].title, feed.links[0].id);
 
@@ -10,9 +10,9 @@ fn main() -> Result<()> {
     let a = add(
         file.clone(),
         "Original",
-        "https://same.url/".into(),
+        "https://same.url/",
         None,
-        Some("t1".into()),
+        Some("t1"),
         None,
         None,
     )?;
@@ -21,16 +21,16 @@ fn main() -> Result<()> {
     let a2 = add(
         file.clone(),
         "Original (updated)",
-        "https://same.url/".into(),
+        "https://same.url/",
         Some(Summary::new("updated")),
-        Some("t2".into()),
+        Some("t2"),
         None,
         None,
     )?;
 
     assert_eq!(a.id, a2.id);
 
-    let feed = list(&file, None, None)?;
+    let feed = list(&file, None)?;
     println!(
         "front item: {} [{}] tags: {:?}",
         feed.links[0].title, feed.links[0].id, feed.links[0].tags
@@ -0,0 +1,507 @@
+//! Atom 1.0 ([RFC 4287](https://www.rfc-editor.org/rfc/rfc4287)) export.
+//!
+//! The Atom counterpart of [`feed_to_rss_xml`](crate::feed_to_rss_xml). Each
+//! link becomes an `<entry>`:
+//!
+//! | Atom            | Link                                             |
+//! |-----------------|--------------------------------------------------|
+//! | `id`            | `urn:uuid:` + `id`; see below for other ids      |
+//! | `title`         | `title`                                          |
+//! | `updated`       | `updated`, else `created`, else `datetime`       |
+//! | `published`     | `created`, else `datetime`                       |
+//! | `link`          | `url` (`rel="alternate"`), `via` (`rel="via"`)   |
+//! | `category`      | one per tag (`term`)                             |
+//! | `summary`       | `summary` (plain text)                           |
+//!
+//! Links whose `id` is not a UUID get a [tag URI](https://www.rfc-editor.org/rfc/rfc4151)
+//! under the host of the feed id, dated the day the link was created
+//! (`tag:example.com,2025-03-14:my-id`). Links without such an id, or whose
+//! id is already taken by an earlier entry, get a name-based `urn:uuid:`
+//! (UUID version 8) derived from the feed id, their `id`, `url` and a
+//! counter, so entry ids are never empty and unique within the feed.
+
+use crate::canonical::split_url;
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::linkleaf_proto::{DateTime, Feed, Link};
+use atom_syndication::{
+    CategoryBuilder, EntryBuilder, FeedBuilder, FixedDateTime, GeneratorBuilder, LinkBuilder,
+    PersonBuilder, Text, WriteConfig,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use time::Date;
+use uuid::{Builder, Uuid};
+
+/// Options for [`feed_to_atom_xml`].
+///
+/// Options compare equal if everything but their [`clock`](AtomOptions::clock)
+/// is equal.
+#[derive(Debug, Clone, Default)]
+pub struct AtomOptions {
+    /// Feed title if `Feed.title` is empty.
+    pub site_title: String,
+    /// The site the feed belongs to, written as the feed's
+    /// `<link rel="alternate">`. Optional.
+    pub site_link: String,
+    /// Permanent, unique IRI of the feed (`<feed><id>`). Defaults to
+    /// `site_link`.
+    pub id: Option<String>,
+    /// Feed `<author>` name. Defaults to the feed title. Atom requires an
+    /// author, and entries have none of their own.
+    pub author: Option<String>,
+    /// Also export links that are in the trash.
+    pub include_trashed: bool,
+    /// Where the `updated` time of a feed without entries comes from.
+    /// `None` (the default) uses a [`SystemClock`].
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+impl PartialEq for AtomOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.site_title == other.site_title
+            && self.site_link == other.site_link
+            && self.id == other.id
+            && self.author == other.author
+            && self.include_trashed == other.include_trashed
+    }
+}
+
+impl Eq for AtomOptions {}
+
+/// Converts a `Feed` into an Atom 1.0 XML string.
+///
+/// ## Behavior
+/// - Entries follow the feed order (newest first); see the
+///   [module docs](crate::atom) for how link fields map to Atom elements.
+/// - Trashed links are skipped unless [`AtomOptions::include_trashed`] is set.
+/// - Timestamps keep their UTC offset; values without one are written as UTC.
+///   A link without any valid timestamp gets the feed's `updated`.
+/// - The feed's `updated` is the latest entry `updated`, or now (see
+///   [`AtomOptions::clock`]) for a feed without entries.
+/// - The XML is pretty-printed with an indentation of 2 spaces.
+///
+/// ## Errors
+/// - [`Error::Validation`] if neither [`AtomOptions::id`] nor
+///   [`AtomOptions::site_link`] is set (Atom requires a feed id).
+/// - [`Error::Export`] if the XML cannot be written.
+///
+/// ## Example
+/// ```
+/// use linkleaf_core::linkleaf_proto::Feed;
+/// use linkleaf_core::{AtomOptions, feed_to_atom_xml};
+///
+/// let feed = Feed { title: "My Links".into(), ..Default::default() };
+/// let options = AtomOptions {
+///     site_link: "https://example.com/".into(),
+///     ..Default::default()
+/// };
+/// let xml = feed_to_atom_xml(&feed, &options)?;
+/// assert!(xml.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+/// # Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn feed_to_atom_xml(feed: &Feed, options: &AtomOptions) -> Result<String> {
+    let id = options
+        .id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .or_else(|| Some(options.site_link.clone()).filter(|l| !l.trim().is_empty()))
+        .ok_or_else(|| {
+            Error::Validation("an Atom feed needs an id: set AtomOptions::id or site_link".into())
+        })?;
+    let title = if feed.title.is_empty() {
+        options.site_title.clone()
+    } else {
+        feed.title.clone()
+    };
+
+    let links: Vec<&Link> = feed
+        .links
+        .iter()
+        .filter(|l| options.include_trashed || l.deleted.is_none())
+        .collect();
+    let feed_updated = links
+        .iter()
+        .filter_map(|l| last_modified(l))
+        .max()
+        .or_else(|| match &options.clock {
+            Some(clock) => to_fixed(&clock.now()),
+            None => to_fixed(&SystemClock.now()),
+        })
+        .unwrap_or_default();
+    let id_host = split_url(&id)
+        .map(|parts| parts.host.to_ascii_lowercase())
+        .filter(|host| !host.is_empty() && !host.starts_with('['))
+        .unwrap_or_default();
+    let mut used_ids = HashSet::new();
+    let entries = links
+        .into_iter()
+        .map(|l| {
+            let mut entry_id = entry_id(l, &id_host);
+            let mut n = 0;
+            while entry_id.as_ref().is_none_or(|e| used_ids.contains(e)) {
+                entry_id = Some(hashed_entry_id(&id, l, n));
+                n += 1;
+            }
+            let entry_id = entry_id.expect("set by the loop");
+            used_ids.insert(entry_id.clone());
+            link_to_atom_entry(l, entry_id, feed_updated)
+        })
+        .collect::<Vec<_>>();
+
+    let mut site_links = Vec::new();
+    if !options.site_link.is_empty() {
+        site_links.push(LinkBuilder::default().href(&options.site_link).build());
+    }
+
+    let atom = FeedBuilder::default()
+        .title(Text::plain(&title))
+        .id(id)
+        .updated(feed_updated)
+        .author(
+            PersonBuilder::default()
+                .name(options.author.clone().unwrap_or(title))
+                .build(),
+        )
+        .generator(
+            GeneratorBuilder::default()
+                .value("Linkleaf")
+                .version(env!("CARGO_PKG_VERSION").to_string())
+                .build(),
+        )
+        .links(site_links)
+        .entries(entries)
+        .build();
+
+    let config = WriteConfig {
+        write_document_declaration: true,
+        indent_size: Some(2),
+    };
+    let buf = atom.write_with_config(Vec::new(), config)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+fn link_to_atom_entry(
+    l: &Link,
+    id: String,
+    fallback_updated: FixedDateTime,
+) -> atom_syndication::Entry {
+    let mut links = vec![LinkBuilder::default().href(&l.url).build()];
+    if let Some(via) = l.via.as_ref().filter(|v| !v.url.is_empty()) {
+        links.push(LinkBuilder::default().href(&via.url).rel("via").build());
+    }
+    let categories = l
+        .tags
+        .iter()
+        .map(|t| CategoryBuilder::default().term(t).build())
+        .collect::<Vec<_>>();
+
+    EntryBuilder::default()
+        .id(id)
+        .title(Text::plain(&l.title))
+        .updated(last_modified(l).unwrap_or(fallback_updated))
+        .published(
+            l.created
+                .as_ref()
+                .or(l.datetime.as_ref())
+                .and_then(to_fixed),
+        )
+        .links(links)
+        .categories(categories)
+        .summary(
+            l.summary
+                .as_ref()
+                .filter(|s| !s.content.is_empty())
+                .map(|s| Text::plain(&s.content)),
+        )
+        .build()
+}
+
+/// `urn:uuid:` for UUID ids, else a tag URI minted under `id_host` on the
+/// day the link was created, if there is one.
+fn entry_id(l: &Link, id_host: &str) -> Option<String> {
+    if let Ok(uuid) = Uuid::parse_str(&l.id) {
+        return Some(uuid.urn().to_string());
+    }
+    let created = l
+        .created
+        .as_ref()
+        .or(l.datetime.as_ref())
+        .and_then(|dt| Date::try_from(dt).ok());
+    match created {
+        Some(date) if !id_host.is_empty() && !l.id.is_empty() => Some(format!(
+            "tag:{id_host},{date}:{}",
+            escape_tag_specific(&l.id)
+        )),
+        _ => None,
+    }
+}
+
+/// `urn:uuid:` of a version 8 UUID hashed (128-bit FNV-1a) from the feed
+/// id, the link's `id` and `url`, and `n`.
+fn hashed_entry_id(feed_id: &str, l: &Link, n: u32) -> String {
+    const OFFSET: u128 = 0x6c62272e_07bb0142_62b82175_6295c58d;
+    const PRIME: u128 = 0x00000000_01000000_00000000_0000013b;
+    let mut hash = OFFSET;
+    let n = n.to_be_bytes();
+    for part in [feed_id.as_bytes(), l.id.as_bytes(), l.url.as_bytes(), &n] {
+        // 0xff never occurs in UTF-8, so it separates the parts.
+        for &b in part.iter().chain(&[0xff]) {
+            hash = (hash ^ u128::from(b)).wrapping_mul(PRIME);
+        }
+    }
+    Builder::from_custom_bytes(hash.to_be_bytes())
+        .into_uuid()
+        .urn()
+        .to_string()
+}
+
+/// Percent-encode what may not appear in the specific part of a tag URI.
+fn escape_tag_specific(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/?".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// `updated`, else `created`, else `datetime`; the first valid one.
+fn last_modified(l: &Link) -> Option<FixedDateTime> {
+    [&l.updated, &l.created, &l.datetime]
+        .into_iter()
+        .find_map(|dt| dt.as_ref().and_then(to_fixed))
+}
+
+fn to_fixed(dt: &DateTime) -> Option<FixedDateTime> {
+    FixedDateTime::try_from(dt).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AtomOptions, feed_to_atom_xml};
+    use crate::clock::FixedClock;
+    use crate::error::Error;
+    use crate::linkleaf_proto::{DateTime, Feed, Link, Summary, Via};
+    use anyhow::Result;
+    use std::sync::Arc;
+
+    fn link(id: &str, title: &str, updated: &str) -> Result<Link> {
+        let updated = DateTime::parse(updated)?;
+        Ok(Link {
+            id: id.into(),
+            title: title.into(),
+            url: format!("https://example.com/{title}"),
+            datetime: Some(updated),
+            created: Some(updated),
+            updated: Some(updated),
+            ..Default::default()
+        })
+    }
+
+    fn sample_feed() -> Result<Feed> {
+        let mut tokio = link(
+            "0f8fad5b-d9cb-469f-a165-70867728950e",
+            "tokio",
+            "2025-03-14T09:26:53+01:00",
+        )?;
+        tokio.tags = vec!["rust".into(), "lang/async".into()];
+        tokio.summary = Some(Summary::new("Async <runtime> & more"));
+        tokio.via = Some(Via::new("https://news.example.com/item?id=1&x=2"));
+        tokio.created = Some(DateTime::parse("2025-03-01 08:00:00")?);
+
+        let mut trashed = link(
+            "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+            "old",
+            "2025-03-20T00:00:00Z",
+        )?;
+        trashed.deleted = trashed.updated;
+
+        Ok(Feed {
+            title: "My Links".into(),
+            version: 2,
+            links: vec![tokio, trashed],
+            ..Default::default()
+        })
+    }
+
+    /// Checks the parts of the RFC 4287 schema a feed reader relies on.
+    fn assert_atom_shape(feed: &atom_syndication::Feed) {
+        assert!(!feed.id.is_empty(), "atom:feed needs atom:id");
+        assert!(!feed.title.value.is_empty(), "atom:feed needs atom:title");
+        assert!(!feed.authors.is_empty(), "atom:feed needs atom:author");
+        let mut ids = std::collections::HashSet::new();
+        for e in &feed.entries {
+            assert!(!e.id.is_empty(), "atom:entry needs atom:id");
+            assert!(ids.insert(&e.id), "atom:id {} is not unique", e.id);
+            if let Some(uuid) = e.id.strip_prefix("urn:uuid:") {
+                uuid::Uuid::parse_str(uuid).expect("urn:uuid: id is a UUID");
+            }
+            assert!(!e.title.value.is_empty(), "atom:entry needs atom:title");
+            assert!(e.updated <= feed.updated, "feed updated is the latest");
+            assert!(
+                e.links.iter().any(|l| l.rel == "alternate"),
+                "atom:entry without content needs a rel=alternate link"
+            );
+            for c in &e.categories {
+                assert!(!c.term.is_empty(), "atom:category needs a term");
+            }
+        }
+    }
+
+    #[test]
+    fn exports_valid_atom() -> Result<()> {
+        let options = AtomOptions {
+            site_link: "https://example.com/".into(),
+            ..Default::default()
+        };
+        let xml = feed_to_atom_xml(&sample_feed()?, &options)?;
+        assert!(xml.starts_with(r#"<?xml version="1.0"?>"#), "{xml}");
+        assert!(xml.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+
+        let parsed: atom_syndication::Feed = xml.parse()?;
+        assert_atom_shape(&parsed);
+        assert_eq!(parsed.id, "https://example.com/");
+        assert_eq!(parsed.title.value, "My Links");
+        assert_eq!(parsed.authors[0].name, "My Links");
+        assert_eq!(parsed.entries.len(), 1, "trashed link skipped");
+
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.id, "urn:uuid:0f8fad5b-d9cb-469f-a165-70867728950e");
+        assert_eq!(entry.updated.to_rfc3339(), "2025-03-14T09:26:53+01:00");
+        assert_eq!(
+            entry.published.map(|p| p.to_rfc3339()).as_deref(),
+            Some("2025-03-01T08:00:00+00:00"),
+            "no offset: written as UTC"
+        );
+        assert_eq!(parsed.updated, entry.updated);
+        let terms: Vec<_> = entry.categories.iter().map(|c| c.term.as_str()).collect();
+        assert_eq!(terms, ["rust", "lang/async"]);
+        assert_eq!(
+            entry.summary.as_ref().map(|s| s.value.as_str()),
+            Some("Async <runtime> & more")
+        );
+        let links: Vec<_> = entry
+            .links
+            .iter()
+            .map(|l| (l.rel.as_str(), l.href.as_str()))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                ("alternate", "https://example.com/tokio"),
+                ("via", "https://news.example.com/item?id=1&x=2"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn options_control_id_author_and_trash() -> Result<()> {
+        let mut feed = sample_feed()?;
+        feed.title.clear();
+        let options = AtomOptions {
+            site_title: "Fallback".into(),
+            id: Some("urn:uuid:1b4e28ba-2fa1-11d2-883f-0016d3cca427".into()),
+            author: Some("Alice".into()),
+            include_trashed: true,
+            ..Default::default()
+        };
+        let parsed: atom_syndication::Feed = feed_to_atom_xml(&feed, &options)?.parse()?;
+        assert_atom_shape(&parsed);
+        assert_eq!(parsed.id, "urn:uuid:1b4e28ba-2fa1-11d2-883f-0016d3cca427");
+        assert_eq!(parsed.title.value, "Fallback");
+        assert_eq!(parsed.authors[0].name, "Alice");
+        assert!(parsed.links.is_empty());
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.updated.to_rfc3339(), "2025-03-20T00:00:00+00:00");
+
+        let err = feed_to_atom_xml(&feed, &AtomOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn non_uuid_ids_get_a_tag_uri_or_a_hashed_uuid() -> Result<()> {
+        let mut custom = link("my id/1", "custom", "2025-03-14T09:26:53+01:00")?;
+        custom.created = Some(DateTime::parse("2025-03-01T23:30:00-05:00")?);
+        let mut undated = link("0f8fad5b", "undated", "2025-03-14T09:26:53Z")?;
+        undated.created = None;
+        undated.datetime = None;
+        let feed = Feed {
+            title: "My Links".into(),
+            links: vec![custom, undated],
+            ..Default::default()
+        };
+        let options = AtomOptions {
+            site_link: "https://Example.com/links".into(),
+            ..Default::default()
+        };
+
+        let parsed: atom_syndication::Feed = feed_to_atom_xml(&feed, &options)?.parse()?;
+        assert_atom_shape(&parsed);
+        assert_eq!(parsed.entries[0].id, "tag:example.com,2025-03-01:my%20id/1");
+        let undated = parsed.entries[1].id.strip_prefix("urn:uuid:").unwrap();
+        assert_eq!(uuid::Uuid::parse_str(undated)?.get_version_num(), 8);
+        Ok(())
+    }
+
+    #[test]
+    fn entry_ids_are_unique_and_stable() -> Result<()> {
+        let uuid = "0f8fad5b-d9cb-469f-a165-70867728950e";
+        let mut links = vec![
+            link(uuid, "a", "2025-03-05T00:00:00Z")?,
+            link(uuid, "b", "2025-03-04T00:00:00Z")?,
+            link("", "c", "2025-03-03T00:00:00Z")?,
+            link("", "d", "2025-03-02T00:00:00Z")?,
+            link("same", "e", "2025-03-01T00:00:00Z")?,
+            link("same", "f", "2025-03-01T00:00:00Z")?,
+        ];
+        for l in &mut links[2..4] {
+            l.url.clear();
+        }
+        for l in &mut links[4..] {
+            l.url = "https://example.com/dup".into();
+        }
+        let feed = Feed {
+            title: "My Links".into(),
+            links,
+            ..Default::default()
+        };
+        let options = AtomOptions {
+            id: Some("urn:uuid:1b4e28ba-2fa1-11d2-883f-0016d3cca427".into()),
+            ..Default::default()
+        };
+
+        let xml = feed_to_atom_xml(&feed, &options)?;
+        assert_eq!(xml, feed_to_atom_xml(&feed, &options)?, "stable");
+        let parsed: atom_syndication::Feed = xml.parse()?;
+        assert_atom_shape(&parsed);
+        assert_eq!(parsed.entries.len(), 6);
+        assert_eq!(parsed.entries[0].id, format!("urn:uuid:{uuid}"));
+        for e in &parsed.entries[1..] {
+            assert!(e.id.starts_with("urn:uuid:"), "{}", e.id);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn empty_feed_is_updated_at_the_clock_time() -> Result<()> {
+        let at = DateTime::parse("2025-03-14T09:26:53+01:00")?;
+        let options = AtomOptions {
+            site_link: "https://example.com/".into(),
+            clock: Some(Arc::new(FixedClock(at))),
+            ..Default::default()
+        };
+        let feed = Feed::default();
+        let first = feed_to_atom_xml(&feed, &options)?;
+        assert_eq!(first, feed_to_atom_xml(&feed, &options)?);
+        let parsed: atom_syndication::Feed = first.parse()?;
+        assert_eq!(parsed.updated.to_rfc3339(), "2025-03-14T09:26:53+01:00");
+        assert!(parsed.entries.is_empty());
+        Ok(())
+    }
+}
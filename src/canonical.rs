@@ -0,0 +1,217 @@
+//! URL canonicalization, used to recognize the same link behind different
+//! spellings of its URL.
+//!
+//! The canonical form is only used for comparisons; links keep the URL they
+//! were added with.
+//!
+//! ```
+//! use linkleaf_core::canonical::canonicalize_url;
+//!
+//! assert_eq!(canonicalize_url("https://TOKIO.rs:443/?utm_source=x#intro"), "https://tokio.rs/");
+//! assert_eq!(canonicalize_url("https://tokio.rs"), "https://tokio.rs/");
+//! ```
+
+/// Query parameters removed by [`CanonicalOptions::default`]. A trailing `*`
+/// matches any parameter name with that prefix.
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "mc_cid",
+    "mc_eid", "igshid", "_hsenc", "_hsmi", "mkt_tok", "ref_src",
+];
+
+/// How [`canonicalize_url_with`] rewrites a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalOptions {
+    /// Query parameter names to drop, compared case-insensitively. A trailing
+    /// `*` makes the entry a prefix (`utm_*`).
+    pub strip_params: Vec<String>,
+    /// Treat `http` and `https` as the same scheme (canonical form uses
+    /// `https`). Defaults to `false`: a site may serve different content
+    /// over each.
+    pub fold_http: bool,
+    /// Drop a trailing `/` from non-root paths (`/blog/` → `/blog`). The root
+    /// path is always `/`. Defaults to `true`.
+    pub strip_trailing_slash: bool,
+}
+
+impl Default for CanonicalOptions {
+    fn default() -> Self {
+        CanonicalOptions {
+            strip_params: DEFAULT_TRACKING_PARAMS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            fold_http: false,
+            strip_trailing_slash: true,
+        }
+    }
+}
+
+impl CanonicalOptions {
+    fn strips(&self, param: &str) -> bool {
+        self.strip_params.iter().any(|p| match p.strip_suffix('*') {
+            Some(prefix) => param
+                .get(..prefix.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+            None => param.eq_ignore_ascii_case(p),
+        })
+    }
+}
+
+/// [`canonicalize_url_with`] using [`CanonicalOptions::default`].
+pub fn canonicalize_url(url: &str) -> String {
+    canonicalize_url_with(url, &CanonicalOptions::default())
+}
+
+/// Canonical form of `url`.
+///
+/// ## Behavior
+/// - Lowercases the scheme and host, and drops the port if it is the default
+///   for the scheme (`80` for http, `443` for https).
+/// - Removes the fragment and the configured tracking parameters; the other
+///   parameters keep their order.
+/// - Normalizes the path's trailing slash (see [`CanonicalOptions`]).
+/// - Strings without a `scheme://` part are returned trimmed but otherwise
+///   unchanged.
+pub fn canonicalize_url_with(url: &str, options: &CanonicalOptions) -> String {
+    let url = url.trim();
+    let Some(UrlParts {
+        scheme,
+        userinfo,
+        host,
+        port,
+        rest,
+    }) = split_url(url)
+    else {
+        return url.to_string();
+    };
+    let scheme = scheme.to_ascii_lowercase();
+
+    let rest = rest.split_once('#').map_or(rest, |(before, _)| before);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let port = port.filter(|p| !p.is_empty() && !is_default_port(&scheme, p));
+    let scheme = if options.fold_http && scheme == "http" {
+        "https"
+    } else {
+        &scheme
+    };
+
+    let mut path = if path.is_empty() { "/" } else { path };
+    if options.strip_trailing_slash && path.len() > 1 {
+        path = path.trim_end_matches('/');
+        if path.is_empty() {
+            path = "/";
+        }
+    }
+
+    let query: Vec<&str> = query
+        .split('&')
+        .filter(|kv| !kv.is_empty())
+        .filter(|kv| !options.strips(kv.split('=').next().unwrap_or_default()))
+        .collect();
+
+    let mut out = format!("{scheme}://");
+    if let Some(u) = userinfo {
+        out.push_str(u);
+        out.push('@');
+    }
+    out.push_str(&host.to_ascii_lowercase());
+    if let Some(p) = port {
+        out.push(':');
+        out.push_str(p);
+    }
+    out.push_str(path);
+    if !query.is_empty() {
+        out.push('?');
+        out.push_str(&query.join("&"));
+    }
+    out
+}
+
+/// The parts of a `scheme://[userinfo@]host[:port][/path][?query][#fragment]`
+/// URL, as written.
+pub(crate) struct UrlParts<'a> {
+    pub scheme: &'a str,
+    pub userinfo: Option<&'a str>,
+    /// IPv6 literals keep their brackets (`[::1]`).
+    pub host: &'a str,
+    /// Whatever follows the host in the authority, without the `:`; may be
+    /// empty or not a number.
+    pub port: Option<&'a str>,
+    /// Path, query and fragment.
+    pub rest: &'a str,
+}
+
+/// Split `url` into its [`UrlParts`]; `None` without a `scheme://` part.
+/// Nothing is validated.
+pub(crate) fn split_url(url: &str) -> Option<UrlParts<'_>> {
+    let (scheme, rest) = url.split_once("://")?;
+    let auth_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, rest) = rest.split_at(auth_end);
+    let (userinfo, hostport) = match authority.rsplit_once('@') {
+        Some((u, h)) => (Some(u), h),
+        None => (None, authority),
+    };
+    let host_end = if hostport.starts_with('[') {
+        hostport.find(']').map_or(hostport.len(), |i| i + 1)
+    } else {
+        hostport.find(':').unwrap_or(hostport.len())
+    };
+    let (host, port) = hostport.split_at(host_end);
+    let port = (!port.is_empty()).then(|| port.strip_prefix(':').unwrap_or(port));
+    Some(UrlParts {
+        scheme,
+        userinfo,
+        host,
+        port,
+        rest,
+    })
+}
+
+fn is_default_port(scheme: &str, port: &str) -> bool {
+    matches!((scheme, port), ("http", "80") | ("https", "443"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanonicalOptions, canonicalize_url, canonicalize_url_with};
+
+    #[test]
+    fn equivalent_spellings_share_a_canonical_form() {
+        let canon = canonicalize_url("https://tokio.rs/");
+        for url in [
+            "https://tokio.rs",
+            "https://TOKIO.rs/?utm_source=x",
+            "HTTPS://tokio.rs:443/#top",
+            "  https://tokio.rs/?fbclid=abc&UTM_medium=y ",
+        ] {
+            assert_eq!(canonicalize_url(url), canon, "{url}");
+        }
+        assert_eq!(canonicalize_url("http://tokio.rs:80"), "http://tokio.rs/");
+    }
+
+    #[test]
+    fn keeps_meaningful_parts() {
+        assert_eq!(
+            canonicalize_url("https://Example.com:8443/Docs/?page=2&utm_campaign=z&q=a"),
+            "https://example.com:8443/Docs?page=2&q=a"
+        );
+        assert_eq!(
+            canonicalize_url("https://user@[::1]:443/x/"),
+            "https://user@[::1]/x"
+        );
+        assert_eq!(canonicalize_url(" not a url "), "not a url");
+    }
+
+    #[test]
+    fn options_are_configurable() {
+        let options = CanonicalOptions {
+            strip_params: vec!["ref".into()],
+            fold_http: true,
+            strip_trailing_slash: false,
+        };
+        assert_eq!(
+            canonicalize_url_with("http://a.example:80/b/?ref=hn&utm_source=x", &options),
+            "https://a.example/b/?utm_source=x"
+        );
+    }
+}
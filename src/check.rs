@@ -0,0 +1,430 @@
+//! Integrity checks for feeds (`fsck` for `.pb` files).
+//!
+//! [`check`] reports everything that is inconsistent in a [`Feed`];
+//! [`repair`] fixes the issues that can be fixed without guessing (see
+//! [`Issue::is_repairable`]).
+
+use crate::linkleaf_proto::{DateTime, Feed, Link};
+use crate::migrations::{self, CURRENT_VERSION};
+use crate::store::instant_key;
+use crate::tags::same_tag;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fmt;
+use time::Month;
+use uuid::Uuid;
+
+/// Feed format versions this crate understands (older ones are upgraded by
+/// [`migrations::migrate`]).
+pub const SUPPORTED_VERSIONS: std::ops::RangeInclusive<u32> = 1..=CURRENT_VERSION;
+
+/// One problem found by [`check`]. `position` is the link's index in
+/// `Feed.links`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Issue {
+    /// `Feed.version` is not in [`SUPPORTED_VERSIONS`].
+    UnknownVersion { version: u32 },
+    /// The link has no id.
+    EmptyId { position: usize },
+    /// The id is not a UUID.
+    NonUuidId { position: usize, id: String },
+    /// Another link earlier in the feed has the same id.
+    DuplicateId { position: usize, id: String },
+    /// The title is blank.
+    EmptyTitle { position: usize, id: String },
+    /// The URL is blank.
+    EmptyUrl { position: usize, id: String },
+    /// A timestamp field holds an impossible date or time.
+    InvalidDateTime {
+        position: usize,
+        id: String,
+        /// `datetime`, `created`, `updated` or `deleted`.
+        field: &'static str,
+        reason: String,
+    },
+    /// The link was created (`created`, else `datetime`) after a link before
+    /// it was put in its place, so the feed is not newest-first. A link is
+    /// placed when it is created or, moving to the front, updated (see
+    /// [`AddOptions::move_to_front`](crate::AddOptions::move_to_front)); its
+    /// `updated` bounds when that last happened.
+    OutOfOrder { position: usize, id: String },
+    /// The link carries the same tag more than once (compared folded, see
+    /// [`same_tag`]).
+    DuplicateTag {
+        position: usize,
+        id: String,
+        tag: String,
+    },
+}
+
+impl Issue {
+    /// `true` if [`repair`] fixes this issue:
+    /// - version `0` (unset) becomes the current version;
+    /// - empty and duplicate ids get a fresh UUID v4 (the first link with a
+    ///   given id keeps it);
+    /// - duplicate tags are dropped, keeping the first spelling;
+    /// - out-of-order links are re-sorted by their later of `created` and
+    ///   `updated`, newest first (stable).
+    ///
+    /// Other issues need a human decision and are only reported.
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            Issue::UnknownVersion { version } => *version == 0,
+            Issue::EmptyId { .. }
+            | Issue::DuplicateId { .. }
+            | Issue::DuplicateTag { .. }
+            | Issue::OutOfOrder { .. } => true,
+            Issue::NonUuidId { .. }
+            | Issue::EmptyTitle { .. }
+            | Issue::EmptyUrl { .. }
+            | Issue::InvalidDateTime { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnknownVersion { version } => write!(f, "unknown feed version {version}"),
+            Issue::EmptyId { position } => write!(f, "link #{position}: empty id"),
+            Issue::NonUuidId { position, id } => {
+                write!(f, "link #{position}: id {id:?} is not a UUID")
+            }
+            Issue::DuplicateId { position, id } => {
+                write!(f, "link #{position}: duplicate id {id:?}")
+            }
+            Issue::EmptyTitle { position, id } => write!(f, "link #{position} ({id}): empty title"),
+            Issue::EmptyUrl { position, id } => write!(f, "link #{position} ({id}): empty url"),
+            Issue::InvalidDateTime {
+                position,
+                id,
+                field,
+                reason,
+            } => write!(f, "link #{position} ({id}): invalid {field}: {reason}"),
+            Issue::OutOfOrder { position, id } => {
+                write!(f, "link #{position} ({id}): created after a link before it")
+            }
+            Issue::DuplicateTag { position, id, tag } => {
+                write!(f, "link #{position} ({id}): duplicate tag {tag:?}")
+            }
+        }
+    }
+}
+
+/// Report every integrity issue in `feed`, in feed order (feed-level issues
+/// first).
+pub fn check(feed: &Feed) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if !SUPPORTED_VERSIONS.contains(&feed.version) {
+        issues.push(Issue::UnknownVersion {
+            version: feed.version,
+        });
+    }
+
+    let mut seen_ids: HashSet<&str> = HashSet::new();
+    // Latest time at which every earlier link can have been placed.
+    let mut placed_before = None;
+    for (position, link) in feed.links.iter().enumerate() {
+        let id = link.id.clone();
+        if link.id.is_empty() {
+            issues.push(Issue::EmptyId { position });
+        } else {
+            if Uuid::parse_str(&link.id).is_err() {
+                issues.push(Issue::NonUuidId {
+                    position,
+                    id: id.clone(),
+                });
+            }
+            if !seen_ids.insert(&link.id) {
+                issues.push(Issue::DuplicateId {
+                    position,
+                    id: id.clone(),
+                });
+            }
+        }
+        if link.title.trim().is_empty() {
+            issues.push(Issue::EmptyTitle {
+                position,
+                id: id.clone(),
+            });
+        }
+        if link.url.trim().is_empty() {
+            issues.push(Issue::EmptyUrl {
+                position,
+                id: id.clone(),
+            });
+        }
+        for (field, value) in [
+            ("datetime", &link.datetime),
+            ("created", &link.created),
+            ("updated", &link.updated),
+            ("deleted", &link.deleted),
+        ] {
+            if let Some(Err(reason)) = value.as_ref().map(check_datetime) {
+                issues.push(Issue::InvalidDateTime {
+                    position,
+                    id: id.clone(),
+                    field,
+                    reason,
+                });
+            }
+        }
+
+        if let (Some(created), Some(bound)) = (created_key(link), placed_before)
+            && created > bound
+        {
+            issues.push(Issue::OutOfOrder {
+                position,
+                id: id.clone(),
+            });
+        }
+        placed_before = match (placed_before, placed_key(link)) {
+            (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+
+        let mut tags_seen: Vec<&str> = Vec::new();
+        for tag in &link.tags {
+            if tags_seen.iter().any(|t| same_tag(t, tag)) {
+                issues.push(Issue::DuplicateTag {
+                    position,
+                    id: id.clone(),
+                    tag: tag.clone(),
+                });
+            } else {
+                tags_seen.push(tag);
+            }
+        }
+    }
+    issues
+}
+
+/// Fix the repairable issues of `feed` in place (see [`Issue::is_repairable`]).
+///
+/// ## Returns
+/// The issues that were fixed, as [`check`] reported them before the repair.
+/// Running [`check`] afterwards returns only the remaining, non-repairable ones.
+pub fn repair(feed: &mut Feed) -> Vec<Issue> {
+    let fixed: Vec<Issue> = check(feed)
+        .into_iter()
+        .filter(Issue::is_repairable)
+        .collect();
+    if fixed.is_empty() {
+        return fixed;
+    }
+
+    if feed.version == 0 {
+        // Version 0 is always migratable.
+        let _ = migrations::migrate(feed);
+    }
+
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    for link in &mut feed.links {
+        if link.id.is_empty() || seen_ids.contains(&link.id) {
+            link.id = Uuid::new_v4().to_string();
+        }
+        seen_ids.insert(link.id.clone());
+
+        let mut tags: Vec<String> = Vec::with_capacity(link.tags.len());
+        for tag in link.tags.drain(..) {
+            if !tags.iter().any(|t| same_tag(t, &tag)) {
+                tags.push(tag);
+            }
+        }
+        link.tags = tags;
+    }
+
+    // Stable: links with equal keys keep their relative order.
+    feed.links.sort_by_key(|l| Reverse(placed_key(l)));
+    fixed
+}
+
+/// When a link was created: `created`, else `datetime`.
+fn created_key(link: &Link) -> Option<(i64, i32)> {
+    link.created
+        .as_ref()
+        .or(link.datetime.as_ref())
+        .map(instant_key)
+}
+
+/// The latest the link can have been put in its place: created, or updated
+/// and moved to the front. Updating in place only makes this later, so it
+/// never puts a feed out of order.
+fn placed_key(link: &Link) -> Option<(i64, i32)> {
+    created_key(link).max(link.updated.as_ref().map(instant_key))
+}
+
+/// Check the ranges of a civil datetime. An all-zero value means "unknown"
+/// and is accepted; year `0` means "no specific year".
+fn check_datetime(dt: &DateTime) -> Result<(), String> {
+    if *dt == DateTime::default() {
+        return Ok(());
+    }
+    if !(0..=9999).contains(&dt.year) {
+        return Err(format!("year {} out of range", dt.year));
+    }
+    let month = u8::try_from(dt.month)
+        .ok()
+        .and_then(|m| Month::try_from(m).ok())
+        .ok_or_else(|| format!("month {} out of range", dt.month))?;
+    // Without a year, allow February 29th.
+    let year = if dt.year == 0 { 2000 } else { dt.year };
+    let max_day = month.length(year);
+    if !(1..=i32::from(max_day)).contains(&dt.day) {
+        return Err(format!(
+            "day {} out of range for month {}",
+            dt.day, dt.month
+        ));
+    }
+    if !(0..=23).contains(&dt.hours) {
+        return Err(format!("hours {} out of range", dt.hours));
+    }
+    if !(0..=59).contains(&dt.minutes) {
+        return Err(format!("minutes {} out of range", dt.minutes));
+    }
+    // 60 allows for leap seconds.
+    if !(0..=60).contains(&dt.seconds) {
+        return Err(format!("seconds {} out of range", dt.seconds));
+    }
+    if !(0..=999_999_999).contains(&dt.nanos) {
+        return Err(format!("nanos {} out of range", dt.nanos));
+    }
+    if let Some(offset) = dt.utc_offset
+        && !(-86_399..=86_399).contains(&offset)
+    {
+        return Err(format!("utc_offset {offset}s out of range"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Issue, check, repair};
+    use crate::linkleaf_proto::{DateTime, Feed, Link};
+    use crate::migrations::CURRENT_VERSION;
+    use uuid::Uuid;
+
+    fn link(id: &str, day: i32) -> Link {
+        let dt = DateTime {
+            year: 2025,
+            month: 1,
+            day,
+            ..Default::default()
+        };
+        Link {
+            id: id.into(),
+            title: "T".into(),
+            url: "https://t/".into(),
+            datetime: Some(dt),
+            created: Some(dt),
+            updated: Some(dt),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clean_feed_has_no_issues() {
+        let feed = Feed {
+            version: 1,
+            links: vec![
+                link(&Uuid::new_v4().to_string(), 2),
+                link(&Uuid::new_v4().to_string(), 1),
+            ],
+            ..Default::default()
+        };
+        assert!(check(&feed).is_empty());
+    }
+
+    #[test]
+    fn reports_each_kind_of_issue() {
+        let id = Uuid::new_v4().to_string();
+        let mut bad_date = link(&Uuid::new_v4().to_string(), 1);
+        bad_date.updated.as_mut().unwrap().month = 13;
+        bad_date.deleted = Some(DateTime {
+            year: 2025,
+            month: 2,
+            day: 29,
+            ..Default::default()
+        });
+        let mut empty = link("", 1);
+        empty.title = " ".into();
+        empty.url.clear();
+        empty.tags = vec!["lang/rust".into(), "Lang / Rust".into()];
+        let feed = Feed {
+            version: 7,
+            links: vec![
+                link(&id, 3),
+                link(&id, 4),
+                link("custom", 2),
+                bad_date,
+                empty,
+            ],
+            ..Default::default()
+        };
+
+        let issues = check(&feed);
+        let has = |pred: &dyn Fn(&Issue) -> bool| issues.iter().any(pred);
+        assert!(has(&|i| matches!(i, Issue::UnknownVersion { version: 7 })));
+        assert!(has(&|i| matches!(
+            i,
+            Issue::DuplicateId { position: 1, .. }
+        )));
+        assert!(has(&|i| matches!(i, Issue::OutOfOrder { position: 1, .. })));
+        assert!(has(&|i| matches!(i, Issue::NonUuidId { position: 2, .. })));
+        assert!(has(&|i| matches!(
+            i,
+            Issue::InvalidDateTime {
+                position: 3,
+                field: "updated",
+                ..
+            }
+        )));
+        assert!(has(&|i| matches!(
+            i,
+            Issue::InvalidDateTime {
+                position: 3,
+                field: "deleted",
+                ..
+            }
+        )));
+        assert!(has(&|i| matches!(i, Issue::EmptyId { position: 4 })));
+        assert!(has(&|i| matches!(i, Issue::EmptyTitle { position: 4, .. })));
+        assert!(has(&|i| matches!(i, Issue::EmptyUrl { position: 4, .. })));
+        assert!(has(&|i| matches!(
+            i,
+            Issue::DuplicateTag { position: 4, .. }
+        )));
+        assert_eq!(issues.len(), 10, "{issues:#?}");
+        assert!(issues[0].to_string().contains("version 7"));
+    }
+
+    #[test]
+    fn repair_fixes_mechanical_issues_only() {
+        let id = Uuid::new_v4().to_string();
+        let mut tagged = link(&id, 1);
+        tagged.tags = vec!["a".into(), "b".into(), "A".into(), " a ".into()];
+        let mut untitled = link("", 3);
+        untitled.title.clear();
+        let feed_links = vec![tagged, link(&id, 2), untitled];
+        let mut feed = Feed {
+            version: 0,
+            links: feed_links,
+            ..Default::default()
+        };
+
+        let fixed = repair(&mut feed);
+        assert!(fixed.iter().all(Issue::is_repairable));
+        assert_eq!(feed.version, CURRENT_VERSION);
+        assert_eq!(feed.links[2].id, id, "first holder keeps the id");
+        assert_eq!(feed.links[2].tags, vec!["a", "b"]);
+
+        let remaining = check(&feed);
+        assert_eq!(remaining.len(), 1, "{remaining:#?}");
+        assert!(matches!(
+            remaining[0],
+            Issue::EmptyTitle { position: 0, .. }
+        ));
+        assert!(repair(&mut feed).is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Where timestamps come from.
+//!
+//! Every timestamp the crate writes (`created`, `updated`, `datetime`, the
+//! `deleted` tombstone) is taken from a [`Clock`]. [`SystemClock`] is the
+//! default; [`FixedClock`] makes tests and reproducible imports deterministic,
+//! and [`UtcClock`] avoids looking up the local offset altogether.
+//!
+//! Set one per call with [`AddOptions::clock`](crate::AddOptions::clock),
+//! [`LinkPatch::clock`](crate::LinkPatch#structfield.clock),
+//! [`TrashOptions::clock`](crate::TrashOptions::clock) and
+//! [`AtomOptions::clock`](crate::AtomOptions::clock), or for a whole
+//! [`FeedStore`](crate::FeedStore) with
+//! [`FeedStore::with_clock`](crate::FeedStore::with_clock).
+//!
+//! ## Example
+//! ```
+//! use linkleaf_core::clock::{Clock, FixedClock};
+//! use linkleaf_core::linkleaf_proto::DateTime;
+//!
+//! let clock = FixedClock(DateTime::parse("2025-03-14T09:26:53+01:00")?);
+//! assert_eq!(clock.now().to_string(), "2025-03-14T09:26:53+01:00");
+//! # Ok::<(), linkleaf_core::Error>(())
+//! ```
+
+use crate::linkleaf_proto::DateTime;
+use std::fmt;
+use std::sync::Arc;
+
+/// A source of the current time.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// The current time, with its UTC offset.
+    fn now(&self) -> DateTime;
+}
+
+/// A shared clock, such as [`AddOptions::clock`](crate::AddOptions::clock).
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> DateTime {
+        (**self).now()
+    }
+}
+
+/// The system time in the local time zone.
+///
+/// Some platforms cannot determine the local UTC offset (for example in
+/// multithreaded programs on Unix). The time is then taken in UTC instead,
+/// with a warning logged under the `logs` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::now_local().unwrap_or_else(|_err| {
+            #[cfg(feature = "logs")]
+            tracing::warn!(error = %_err, "local UTC offset unknown; using UTC");
+            DateTime::now_utc()
+        })
+    }
+}
+
+/// The system time in UTC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UtcClock;
+
+impl Clock for UtcClock {
+    fn now(&self) -> DateTime {
+        DateTime::now_utc()
+    }
+}
+
+/// Always the same time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixedClock(pub DateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, FixedClock, SystemClock, UtcClock};
+    use crate::linkleaf_proto::DateTime;
+
+    #[test]
+    fn clocks_record_an_offset() -> anyhow::Result<()> {
+        let fixed = DateTime::parse("2025-03-14T09:26:53-05:00")?;
+        assert_eq!(FixedClock(fixed).now(), fixed);
+        assert_eq!(UtcClock.now().utc_offset, Some(0));
+        // Local or, if that is unknown, UTC; never missing.
+        let now = SystemClock.now();
+        assert!(now.utc_offset.is_some());
+        assert!(now >= DateTime::parse("2025-01-01T00:00:00Z")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,631 @@
+//! Conversions, parsing, formatting and ordering for the proto [`DateTime`].
+//!
+//! ## Conversions
+//! Conversions into [`DateTime`] keep the civil fields of the source and
+//! record its UTC offset where it has one (`OffsetDateTime`, chrono's
+//! `DateTime<FixedOffset>`; `Timestamp` and `SystemTime` become UTC).
+//! `PrimitiveDateTime` and `Date` carry no offset, so neither does the result.
+//!
+//! Conversions out of [`DateTime`] are fallible and return
+//! [`Error::Validation`] for out-of-range fields. Targets that need an offset
+//! use its `utc_offset`, treating values without one as UTC.
+//!
+//! A leap second (`seconds = 60`, as chrono produces) converts back to chrono
+//! exactly, so RSS and Atom exports write it as `:60`, and it sorts right
+//! after second 59. The `time` types, `SystemTime` and `Timestamp` have no
+//! leap seconds; they get the last nanosecond before it (`23:59:59.999999999`).
+//!
+//! ```
+//! use linkleaf_core::linkleaf_proto::DateTime;
+//! use time::OffsetDateTime;
+//! use time::macros::datetime;
+//!
+//! let dt = DateTime::from(datetime!(2025-03-14 09:26:53 +01:00));
+//! assert_eq!((dt.hours, dt.utc_offset), (9, Some(3600)));
+//! let back = OffsetDateTime::try_from(&dt)?;
+//! assert_eq!(back, datetime!(2025-03-14 08:26:53 UTC));
+//! # Ok::<(), linkleaf_core::Error>(())
+//! ```
+//!
+//! ## Text
+//! [`DateTime::parse`] (also [`FromStr`]) reads ISO-8601 / RFC 3339,
+//! RFC 2822 and the `YYYY-MM-DD HH:MM:SS` form of `feed.proto`. [`Display`]
+//! writes ISO-8601 (RFC 3339 when the offset is known), and the alternate
+//! form `{:#}` writes `YYYY-MM-DD HH:MM:SS`.
+//!
+//! ## Ordering
+//! [`Ord`] orders by instant, honoring offsets like sorting and range filters
+//! do. Values at the same instant but written differently (`10:00+02:00` and
+//! `08:00Z`) are still unequal; they are ordered by their fields.
+//!
+//! [`Display`]: fmt::Display
+
+use crate::error::{Error, Result};
+use crate::linkleaf_proto::DateTime;
+use crate::store::instant_key;
+use chrono::{Datelike, FixedOffset, TimeZone, Timelike};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+impl DateTime {
+    /// The current time in the local time zone, with its UTC offset.
+    ///
+    /// ## Errors
+    /// [`Error::TimeOffset`] if the local UTC offset cannot be determined
+    /// (some platforms refuse to look it up in multithreaded programs).
+    pub fn now_local() -> Result<DateTime> {
+        Ok(OffsetDateTime::now_local()?.into())
+    }
+
+    /// The current time in UTC.
+    pub fn now_utc() -> DateTime {
+        OffsetDateTime::now_utc().into()
+    }
+
+    /// Parse a datetime written in one of the supported text forms.
+    ///
+    /// ## Behavior
+    /// - Trims surrounding whitespace.
+    /// - ISO-8601 / RFC 3339: `YYYY-MM-DD`, optionally followed by `T` (or a
+    ///   space) and `HH:MM[:SS[.fraction]]`, optionally followed by `Z` or an
+    ///   offset `±HH:MM` (`±HHMM` and `±HH` also work).
+    /// - RFC 2822: `Fri, 14 Mar 2025 09:26:53 +0100`, with or without the
+    ///   weekday.
+    /// - Without an offset, the result has none either (local time of the
+    ///   writer); otherwise it records the offset.
+    /// - Seconds may be `60` for a leap second, which only exists at
+    ///   `23:59:60` UTC (values without an offset count as UTC).
+    ///
+    /// ## Errors
+    /// [`Error::Validation`] for unrecognized text and out-of-range fields.
+    ///
+    /// ## Example
+    /// ```
+    /// use linkleaf_core::linkleaf_proto::DateTime;
+    ///
+    /// let a = DateTime::parse("2025-03-14 09:26:53")?;
+    /// assert_eq!((a.day, a.hours, a.utc_offset), (14, 9, None));
+    ///
+    /// let b = DateTime::parse("2025-03-14T09:26:53+01:00")?;
+    /// let c = DateTime::parse("Fri, 14 Mar 2025 08:30:00 +0000")?;
+    /// assert!(b < c);
+    /// assert_eq!(b.to_string(), "2025-03-14T09:26:53+01:00");
+    /// # Ok::<(), linkleaf_core::Error>(())
+    /// ```
+    pub fn parse(s: &str) -> Result<DateTime> {
+        parse_datetime(s).map_err(Error::Validation)
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        DateTime::parse(s)
+    }
+}
+
+/// Parser behind [`DateTime::parse`], with a short message on error.
+pub(crate) fn parse_datetime(s: &str) -> std::result::Result<DateTime, String> {
+    let s = s.trim();
+    let b = s.as_bytes();
+    let dt = if b.len() >= 5 && b[..4].iter().all(u8::is_ascii_digit) && b[4] == b'-' {
+        parse_iso(b).ok_or_else(|| {
+            format!("invalid datetime {s:?}: expected YYYY-MM-DD[THH:MM[:SS]][Z|±HH:MM]")
+        })?
+    } else {
+        chrono::DateTime::parse_from_rfc2822(s)
+            .map_err(|e| format!("invalid datetime {s:?}: {e}"))?
+            .into()
+    };
+    // Time has no leap seconds; check the rest of the fields with `:59`.
+    let mut probe = dt;
+    probe.seconds = probe.seconds.min(59);
+    if let Err(Error::Validation(msg)) = OffsetDateTime::try_from(probe) {
+        return Err(format!("{msg} in {s:?}"));
+    }
+    if dt.seconds == 60 {
+        let utc = i64::from(dt.hours * 3600 + dt.minutes * 60 + 59)
+            - i64::from(dt.utc_offset.unwrap_or(0));
+        if utc.rem_euclid(86_400) != 86_399 {
+            return Err(format!(
+                "invalid datetime {s:?}: a leap second can only be 23:59:60 UTC"
+            ));
+        }
+    }
+    Ok(dt)
+}
+
+/// `YYYY-MM-DD[(T| )HH:MM[:SS[.f]]][Z|±HH[[:]MM]]`. Ranges are checked by the
+/// caller.
+fn parse_iso(b: &[u8]) -> Option<DateTime> {
+    let mut c = Cursor { b, pos: 0 };
+    let mut dt = DateTime {
+        year: c.digits(4)?,
+        ..Default::default()
+    };
+    c.expect(b"-")?;
+    dt.month = c.digits(2)?;
+    c.expect(b"-")?;
+    dt.day = c.digits(2)?;
+
+    if c.expect(b"Tt ").is_some() {
+        dt.hours = c.digits(2)?;
+        c.expect(b":")?;
+        dt.minutes = c.digits(2)?;
+        if c.expect(b":").is_some() {
+            dt.seconds = c.digits(2)?;
+            if c.expect(b".,").is_some() {
+                let start = c.pos;
+                while c.peek().is_some_and(|d| d.is_ascii_digit()) {
+                    c.pos += 1;
+                }
+                let frac = &b[start..c.pos];
+                if frac.is_empty() {
+                    return None;
+                }
+                // Digits past nanoseconds are truncated.
+                let mut nanos = 0;
+                for i in 0..9 {
+                    nanos = nanos * 10 + frac.get(i).map_or(0, |d| i32::from(d - b'0'));
+                }
+                dt.nanos = nanos;
+            }
+        }
+        if c.expect(b"Zz").is_some() {
+            dt.utc_offset = Some(0);
+        } else if let Some(sign) = c.expect(b"+-") {
+            let hours = c.digits(2)?;
+            c.expect(b":");
+            let minutes = if c.peek().is_some() { c.digits(2)? } else { 0 };
+            let seconds = if c.expect(b":").is_some() {
+                c.digits(2)?
+            } else {
+                0
+            };
+            if minutes > 59 || seconds > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60 + seconds;
+            dt.utc_offset = Some(if sign == b'-' { -offset } else { offset });
+        }
+    }
+    c.peek().is_none().then_some(dt)
+}
+
+struct Cursor<'a> {
+    b: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.b.get(self.pos).copied()
+    }
+
+    /// Consume one byte if it is one of `any`.
+    fn expect(&mut self, any: &[u8]) -> Option<u8> {
+        let c = self.peek().filter(|c| any.contains(c))?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    /// Exactly `n` ASCII digits.
+    fn digits(&mut self, n: usize) -> Option<i32> {
+        let digits = self.b.get(self.pos..self.pos + n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += n;
+        Some(digits.iter().fold(0, |v, d| v * 10 + i32::from(d - b'0')))
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// ISO-8601: `2025-03-14T09:26:53.5+01:00`, with `Z` for UTC, no suffix
+    /// without a known offset, and a fraction only if `nanos` is set.
+    ///
+    /// `{:#}` writes the plain `2025-03-14 09:26:53` form instead.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+        if f.alternate() {
+            return write!(
+                f,
+                " {:02}:{:02}:{:02}",
+                self.hours, self.minutes, self.seconds
+            );
+        }
+        write!(
+            f,
+            "T{:02}:{:02}:{:02}",
+            self.hours, self.minutes, self.seconds
+        )?;
+        if self.nanos != 0 {
+            let frac = format!("{:09}", self.nanos);
+            write!(f, ".{}", frac.trim_end_matches('0'))?;
+        }
+        match self.utc_offset {
+            None => Ok(()),
+            Some(0) => f.write_str("Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let abs = offset.unsigned_abs();
+                write!(f, "{sign}{:02}:{:02}", abs / 3600, abs / 60 % 60)?;
+                match abs % 60 {
+                    0 => Ok(()),
+                    s => write!(f, ":{s:02}"),
+                }
+            }
+        }
+    }
+}
+
+// Field-wise equality (prost's `PartialEq`) is an equivalence relation.
+impl Eq for DateTime {}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DateTime {
+    /// By instant (values without a `utc_offset` count as UTC), then by the
+    /// fields so that only equal values compare equal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let fields = |d: &DateTime| {
+            (
+                d.year, d.month, d.day, d.hours, d.minutes, d.seconds, d.nanos,
+            )
+        };
+        instant_key(self)
+            .cmp(&instant_key(other))
+            .then_with(|| fields(self).cmp(&fields(other)))
+            .then_with(|| self.utc_offset.cmp(&other.utc_offset))
+    }
+}
+
+impl From<PrimitiveDateTime> for DateTime {
+    fn from(dt: PrimitiveDateTime) -> Self {
+        DateTime {
+            year: dt.year(),
+            month: u8::from(dt.month()).into(),
+            day: dt.day().into(),
+            hours: dt.hour().into(),
+            minutes: dt.minute().into(),
+            seconds: dt.second().into(),
+            // Always below 1_000_000_000.
+            nanos: dt.nanosecond() as i32,
+            utc_offset: None,
+        }
+    }
+}
+
+impl From<OffsetDateTime> for DateTime {
+    fn from(dt: OffsetDateTime) -> Self {
+        DateTime::from(PrimitiveDateTime::new(dt.date(), dt.time()))
+            .with_utc_offset(dt.offset().whole_seconds())
+    }
+}
+
+impl From<Date> for DateTime {
+    /// Midnight at the start of `date`, without an offset.
+    fn from(date: Date) -> Self {
+        DateTime::from(date.midnight())
+    }
+}
+
+impl From<chrono::DateTime<FixedOffset>> for DateTime {
+    fn from(dt: chrono::DateTime<FixedOffset>) -> Self {
+        // chrono encodes a leap second as nanos >= 1_000_000_000.
+        let (seconds, nanos) = match dt.nanosecond() {
+            n if n >= 1_000_000_000 => (60, n - 1_000_000_000),
+            n => (dt.second(), n),
+        };
+        DateTime {
+            year: dt.year(),
+            month: dt.month() as i32,
+            day: dt.day() as i32,
+            hours: dt.hour() as i32,
+            minutes: dt.minute() as i32,
+            seconds: seconds as i32,
+            nanos: nanos as i32,
+            utc_offset: Some(dt.offset().local_minus_utc()),
+        }
+    }
+}
+
+impl From<SystemTime> for DateTime {
+    /// The instant in UTC.
+    fn from(t: SystemTime) -> Self {
+        OffsetDateTime::from(t).into()
+    }
+}
+
+impl TryFrom<prost_types::Timestamp> for DateTime {
+    type Error = Error;
+
+    /// The instant in UTC.
+    fn try_from(ts: prost_types::Timestamp) -> Result<Self> {
+        let nanos = i128::from(ts.seconds) * 1_000_000_000 + i128::from(ts.nanos);
+        let dt = OffsetDateTime::from_unix_timestamp_nanos(nanos)
+            .map_err(|e| invalid(format!("timestamp {ts}: {e}")))?;
+        Ok(dt.into())
+    }
+}
+
+/// Implement `TryFrom<DateTime>` and `TryFrom<&DateTime>` for `$target`.
+macro_rules! try_from_datetime {
+    ($target:ty, |$dt:ident| $body:expr) => {
+        impl TryFrom<&DateTime> for $target {
+            type Error = Error;
+
+            fn try_from($dt: &DateTime) -> Result<Self> {
+                $body
+            }
+        }
+
+        impl TryFrom<DateTime> for $target {
+            type Error = Error;
+
+            fn try_from(dt: DateTime) -> Result<Self> {
+                <$target>::try_from(&dt)
+            }
+        }
+    };
+}
+
+try_from_datetime!(Date, |dt| {
+    let month = u8::try_from(dt.month)
+        .ok()
+        .and_then(|m| Month::try_from(m).ok())
+        .ok_or_else(|| invalid(format!("month {} out of range", dt.month)))?;
+    let day = u8::try_from(dt.day).map_err(|_| invalid(format!("day {} out of range", dt.day)))?;
+    Date::from_calendar_date(dt.year, month, day).map_err(|e| invalid(e.to_string()))
+});
+
+try_from_datetime!(PrimitiveDateTime, |dt| {
+    let field = |name: &str, v: i32| {
+        u8::try_from(v).map_err(|_| invalid(format!("{name} {v} out of range")))
+    };
+    let mut nanos =
+        u32::try_from(dt.nanos).map_err(|_| invalid(format!("nanos {} out of range", dt.nanos)))?;
+    let mut seconds = field("seconds", dt.seconds)?;
+    if seconds == 60 && nanos < 1_000_000_000 {
+        // No leap seconds in `time`: clamp to the end of second 59.
+        (seconds, nanos) = (59, 999_999_999);
+    }
+    let time = Time::from_hms_nano(
+        field("hours", dt.hours)?,
+        field("minutes", dt.minutes)?,
+        seconds,
+        nanos,
+    )
+    .map_err(|e| invalid(e.to_string()))?;
+    Ok(PrimitiveDateTime::new(Date::try_from(dt)?, time))
+});
+
+try_from_datetime!(OffsetDateTime, |dt| {
+    Ok(PrimitiveDateTime::try_from(dt)?.assume_offset(offset(dt)?))
+});
+
+try_from_datetime!(chrono::DateTime<FixedOffset>, |dt| {
+    let t = OffsetDateTime::try_from(dt)?;
+    let offset = FixedOffset::east_opt(t.offset().whole_seconds())
+        .ok_or_else(|| invalid(format!("utc_offset {} out of range", t.offset())))?;
+    // chrono encodes a leap second as second 59 with nanos >= 1_000_000_000.
+    let nanos = if dt.seconds == 60 {
+        1_000_000_000 + dt.nanos as u32
+    } else {
+        t.nanosecond()
+    };
+    offset
+        .timestamp_opt(t.unix_timestamp(), nanos)
+        .single()
+        .ok_or_else(|| invalid(format!("{t} out of range for chrono")))
+});
+
+try_from_datetime!(SystemTime, |dt| Ok(OffsetDateTime::try_from(dt)?.into()));
+
+try_from_datetime!(prost_types::Timestamp, |dt| {
+    let t = OffsetDateTime::try_from(dt)?;
+    Ok(prost_types::Timestamp {
+        seconds: t.unix_timestamp(),
+        nanos: t.nanosecond() as i32,
+    })
+});
+
+/// The offset to use for `dt`: its own, or UTC if unknown. Like
+/// [`check`](crate::check), offsets must be less than a day.
+fn offset(dt: &DateTime) -> Result<UtcOffset> {
+    let seconds = dt.utc_offset.unwrap_or(0);
+    (-86_399..=86_399)
+        .contains(&seconds)
+        .then(|| UtcOffset::from_whole_seconds(seconds).ok())
+        .flatten()
+        .ok_or_else(|| invalid(format!("utc_offset {seconds}s out of range")))
+}
+
+fn invalid(reason: String) -> Error {
+    Error::Validation(format!("invalid DateTime: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::linkleaf_proto::DateTime;
+    use chrono::FixedOffset;
+    use std::cmp::Ordering;
+    use std::time::{Duration, SystemTime};
+    use time::macros::{date, datetime};
+    use time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+    #[test]
+    fn roundtrips_through_time_types() -> anyhow::Result<()> {
+        let t = datetime!(2024-02-29 23:59:58.5 -05:00);
+        let dt = DateTime::from(t);
+        assert_eq!(
+            (dt.year, dt.month, dt.day, dt.hours, dt.nanos),
+            (2024, 2, 29, 23, 500_000_000)
+        );
+        assert_eq!(dt.utc_offset, Some(-5 * 3600));
+        assert_eq!(OffsetDateTime::try_from(&dt)?, t);
+
+        let naive = DateTime::from(datetime!(2024-02-29 23:59:58));
+        assert_eq!(naive.utc_offset, None);
+        assert_eq!(
+            PrimitiveDateTime::try_from(naive)?,
+            datetime!(2024-02-29 23:59:58)
+        );
+        assert_eq!(
+            Date::try_from(DateTime::from(date!(2025 - 01 - 03)))?,
+            date!(2025 - 01 - 03)
+        );
+
+        let chrono_dt = chrono::DateTime::<FixedOffset>::try_from(&dt)?;
+        assert_eq!(chrono_dt.to_rfc3339(), "2024-02-29T23:59:58.500-05:00");
+        assert_eq!(DateTime::from(chrono_dt), dt);
+        Ok(())
+    }
+
+    #[test]
+    fn leap_seconds_roundtrip_through_chrono() -> anyhow::Result<()> {
+        let leap = chrono::DateTime::parse_from_rfc3339("2016-12-31T23:59:60.25Z")?;
+        let dt = DateTime::from(leap);
+        assert_eq!((dt.seconds, dt.nanos), (60, 250_000_000));
+        assert_eq!(chrono::DateTime::<FixedOffset>::try_from(&dt)?, leap);
+
+        let plus_one = chrono::DateTime::parse_from_rfc3339("2017-01-01T00:59:60+01:00")?;
+        assert_eq!(
+            chrono::DateTime::<FixedOffset>::try_from(DateTime::from(plus_one))?,
+            plus_one
+        );
+
+        // `time` has no leap seconds.
+        assert_eq!(
+            OffsetDateTime::try_from(&dt)?,
+            datetime!(2016-12-31 23:59:59.999_999_999 UTC)
+        );
+        let ts = prost_types::Timestamp::try_from(&dt)?;
+        assert_eq!(ts.nanos, 999_999_999);
+        Ok(())
+    }
+
+    #[test]
+    fn instants_convert_as_utc() -> anyhow::Result<()> {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_740_787_200);
+        let dt = DateTime::from(t);
+        assert_eq!((dt.year, dt.month, dt.day, dt.hours), (2025, 3, 1, 0));
+        assert_eq!(dt.utc_offset, Some(0));
+        assert_eq!(SystemTime::try_from(&dt)?, t);
+
+        let ts = prost_types::Timestamp::try_from(dt.with_utc_offset(3600))?;
+        assert_eq!((ts.seconds, ts.nanos), (1_740_787_200 - 3600, 0));
+        assert_eq!(DateTime::try_from(ts)?.hours, 23);
+
+        // No offset: read as UTC.
+        let mut local = dt;
+        local.utc_offset = None;
+        assert_eq!(SystemTime::try_from(local)?, t);
+        Ok(())
+    }
+
+    #[test]
+    fn parses_iso_rfc3339_rfc2822_and_plain() -> anyhow::Result<()> {
+        let plain = DateTime::parse(" 2025-03-14 09:26:53 ")?;
+        assert_eq!(plain, DateTime::from(datetime!(2025-03-14 09:26:53)));
+        assert_eq!(format!("{plain:#}"), "2025-03-14 09:26:53");
+        assert_eq!(plain.to_string(), "2025-03-14T09:26:53");
+
+        let rfc3339 = DateTime::parse("2025-03-14T09:26:53.250+01:00")?;
+        assert_eq!(
+            rfc3339,
+            DateTime::from(datetime!(2025-03-14 09:26:53.25 +01:00))
+        );
+        assert_eq!(rfc3339.to_string(), "2025-03-14T09:26:53.25+01:00");
+
+        let rfc2822 = DateTime::parse("Fri, 14 Mar 2025 09:26:53 -0530")?;
+        assert_eq!(rfc2822.utc_offset, Some(-(5 * 3600 + 30 * 60)));
+        assert_eq!(rfc2822.to_string(), "2025-03-14T09:26:53-05:30");
+        assert_eq!(
+            rfc2822.to_rfc2822().as_deref(),
+            Some("Fri, 14 Mar 2025 09:26:53 -0530")
+        );
+
+        for s in [
+            "2025-03-14",
+            "2025-03-14T09:26",
+            "2025-12-31T23:59:60Z",
+            "2025-03-14t09:26:53+0100",
+        ] {
+            let dt: DateTime = s.parse()?;
+            assert_eq!(DateTime::parse(&dt.to_string())?, dt, "{s}");
+        }
+        assert_eq!(
+            DateTime::parse("2025-03-14T00:00:00z")?.to_string(),
+            "2025-03-14T00:00:00Z"
+        );
+
+        for bad in [
+            "",
+            "2025-3-14",
+            "2025-02-30 00:00:00",
+            "2025-03-14 24:00:00",
+            "2025-03-14T09:26:53+25:00",
+            "2025-03-14T09:26:53 garbage",
+            "14/03/2025",
+        ] {
+            let err = DateTime::parse(bad).unwrap_err();
+            assert!(matches!(err, Error::Validation(_)), "{bad}: {err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn orders_by_instant_and_only_equal_values_tie() -> anyhow::Result<()> {
+        let berlin = DateTime::parse("2025-03-14T10:00:00+02:00")?;
+        let utc = DateTime::parse("2025-03-14T08:00:00Z")?;
+        let later = DateTime::parse("2025-03-14T08:00:00.000000001Z")?;
+        let naive = DateTime::parse("2025-03-14 07:59:59")?;
+
+        assert_ne!(berlin, utc);
+        assert_ne!(berlin.cmp(&utc), Ordering::Equal);
+        assert_eq!(utc.cmp(&utc), Ordering::Equal);
+
+        let mut v = [later, berlin, naive, utc];
+        v.sort();
+        assert_eq!(v[0], naive);
+        assert_eq!(v[3], later);
+        assert_eq!(v.iter().max(), Some(&later));
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_fields_are_validation_errors() {
+        let bad = |f: fn(&mut DateTime)| {
+            let mut dt = DateTime::from(datetime!(2025-01-31 12:00 UTC));
+            f(&mut dt);
+            OffsetDateTime::try_from(dt).unwrap_err()
+        };
+        for err in [
+            bad(|d| d.month = 13),
+            bad(|d| d.day = 0),
+            bad(|d| d.hours = 24),
+            bad(|d| d.nanos = -1),
+            bad(|d| *d = d.with_utc_offset(100_000)),
+        ] {
+            assert!(matches!(err, Error::Validation(_)), "{err}");
+        }
+        assert!(prost_types::Timestamp::try_from(DateTime::default()).is_err());
+        assert!(
+            DateTime::try_from(prost_types::Timestamp {
+                seconds: i64::MAX,
+                nanos: 0
+            })
+            .is_err()
+        );
+    }
+}
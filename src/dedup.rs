@@ -0,0 +1,422 @@
+//! Finding and merging duplicate links.
+//!
+//! Links are duplicates when their URLs have the same canonical form (see
+//! [`crate::canonical`]) or, optionally, when their titles are identical
+//! after normalization (case, punctuation and whitespace ignored). Grouping
+//! is transitive: if A and B share a URL and B and C share a title, all three
+//! form one group. Trashed links are ignored, and links without a URL are
+//! only grouped by title.
+
+use crate::canonical::{CanonicalOptions, canonicalize_url_with};
+use crate::linkleaf_proto::{Feed, Link};
+use crate::store::instant_key;
+use crate::tags::same_tag;
+use std::collections::HashMap;
+
+/// Which link of a group survives a merge, keeping its `id`, `title`, `url`
+/// and timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keep {
+    /// The link created first (ties: the one stored first).
+    #[default]
+    Oldest,
+    /// The link created last (ties: the one stored first).
+    Newest,
+}
+
+/// How tags are merged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagMerge {
+    /// Union of all tags (compared with [`same_tag`]), survivor's tags first.
+    #[default]
+    Union,
+    /// Survivor's tags only.
+    Survivor,
+}
+
+/// How the summary is merged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SummaryMerge {
+    /// The longest summary of the group.
+    #[default]
+    Longest,
+    /// Survivor's summary only.
+    Survivor,
+}
+
+/// How `via` is merged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViaMerge {
+    /// Survivor's `via` if set, else the first non-empty one in stored order.
+    #[default]
+    FirstNonEmpty,
+    /// Survivor's `via` only.
+    Survivor,
+}
+
+/// How a group of duplicates is folded into one [`Link`].
+///
+/// The default keeps the oldest link, with the union of tags, the longest
+/// summary and the first non-empty `via`. The merged link's `updated` is the
+/// latest `updated` of the group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    pub keep: Keep,
+    pub tags: TagMerge,
+    pub summary: SummaryMerge,
+    pub via: ViaMerge,
+}
+
+/// Options for [`find_duplicates`] and [`merge_duplicates`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupOptions {
+    /// Canonical form URLs are compared in.
+    pub canonical: CanonicalOptions,
+    /// Also group links whose normalized titles are equal.
+    pub match_titles: bool,
+    /// Used by [`merge_duplicates`].
+    pub policy: MergePolicy,
+}
+
+/// Links considered duplicates of each other, in stored order (at least two).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub links: Vec<Link>,
+}
+
+/// Result of merging one [`DuplicateGroup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedGroup {
+    /// The link that replaced the group in the feed.
+    pub merged: Link,
+    /// The original links, in stored order.
+    pub group: DuplicateGroup,
+}
+
+/// Group the duplicate links of `feed`.
+///
+/// ## Returns
+/// One [`DuplicateGroup`] per set of duplicates, ordered by the position of
+/// their first link in the feed. Links without duplicates are not reported.
+pub fn find_duplicates(feed: &Feed, options: &DedupOptions) -> Vec<DuplicateGroup> {
+    group_positions(feed, options)
+        .into_iter()
+        .map(|positions| DuplicateGroup {
+            links: positions.iter().map(|&i| feed.links[i].clone()).collect(),
+        })
+        .collect()
+}
+
+/// Merge every group of duplicates in `feed` into a single link.
+///
+/// ## Behavior
+/// Each merged link takes the place of its survivor (see [`Keep`]), the
+/// slot that matches the `created` timestamp it keeps; the other links of
+/// the group are removed. A feed in `created` order stays in order. See
+/// [`MergePolicy`].
+///
+/// ## Returns
+/// The merged groups (empty if `feed` had no duplicates).
+pub fn merge_duplicates(feed: &mut Feed, options: &DedupOptions) -> Vec<MergedGroup> {
+    let groups = group_positions(feed, options);
+    if groups.is_empty() {
+        return Vec::new();
+    }
+
+    let mut slots: Vec<Option<Link>> = std::mem::take(&mut feed.links)
+        .into_iter()
+        .map(Some)
+        .collect();
+    let mut merged_groups = Vec::with_capacity(groups.len());
+    for positions in groups {
+        let links: Vec<Link> = positions
+            .iter()
+            .map(|&i| slots[i].take().expect("positions are disjoint"))
+            .collect();
+        let merged = merge_links(&links, &options.policy);
+        slots[positions[survivor_index(&links, options.policy.keep)]] = Some(merged.clone());
+        merged_groups.push(MergedGroup {
+            merged,
+            group: DuplicateGroup { links },
+        });
+    }
+    feed.links = slots.into_iter().flatten().collect();
+    merged_groups
+}
+
+/// Fold `links` (in stored order) into one link according to `policy`.
+///
+/// ## Panics
+/// If `links` is empty.
+pub fn merge_links(links: &[Link], policy: &MergePolicy) -> Link {
+    let survivor = &links[survivor_index(links, policy.keep)];
+
+    let mut merged = survivor.clone();
+    merged.updated = links
+        .iter()
+        .filter_map(|l| l.updated.as_ref())
+        .max_by_key(|u| instant_key(u))
+        .or(survivor.updated.as_ref())
+        .cloned();
+
+    if policy.tags == TagMerge::Union {
+        for tag in links.iter().flat_map(|l| &l.tags) {
+            if !merged.tags.iter().any(|t| same_tag(t, tag)) {
+                merged.tags.push(tag.clone());
+            }
+        }
+    }
+    if policy.summary == SummaryMerge::Longest {
+        let summary_len = |l: &&Link| l.summary.as_ref().map_or(0, |s| s.content.chars().count());
+        // Prefer the survivor on ties.
+        let longest = std::iter::once(survivor)
+            .chain(links)
+            .reduce(|best, l| {
+                if summary_len(&l) > summary_len(&best) {
+                    l
+                } else {
+                    best
+                }
+            })
+            .expect("group is not empty");
+        merged.summary = longest.summary.clone();
+    }
+    if policy.via == ViaMerge::FirstNonEmpty
+        && merged.via.as_ref().is_none_or(|v| v.url.is_empty())
+        && let Some(via) = links
+            .iter()
+            .filter_map(|l| l.via.as_ref())
+            .find(|v| !v.url.is_empty())
+    {
+        merged.via = Some(via.clone());
+    }
+    merged
+}
+
+/// Index of the link `keep` selects; the first one on ties.
+///
+/// ## Panics
+/// If `links` is empty.
+fn survivor_index(links: &[Link], keep: Keep) -> usize {
+    let created = |l: &Link| l.created.as_ref().or(l.datetime.as_ref()).map(instant_key);
+    let indexed = links.iter().enumerate();
+    match keep {
+        // `min_by_key` / `max_by_key` disagree on ties; pick explicitly.
+        Keep::Oldest => indexed.reduce(|best, l| {
+            if created(l.1) < created(best.1) {
+                l
+            } else {
+                best
+            }
+        }),
+        Keep::Newest => indexed.reduce(|best, l| {
+            if created(l.1) > created(best.1) {
+                l
+            } else {
+                best
+            }
+        }),
+    }
+    .expect("cannot merge an empty group")
+    .0
+}
+
+/// Positions of the links of each duplicate group (sorted, at least two per
+/// group), groups ordered by their first position.
+fn group_positions(feed: &Feed, options: &DedupOptions) -> Vec<Vec<usize>> {
+    let n = feed.links.len();
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut first_by_key: HashMap<String, usize> = HashMap::new();
+    for (i, link) in feed.links.iter().enumerate() {
+        if link.deleted.is_some() {
+            continue;
+        }
+        let mut keys = Vec::new();
+        let url = canonicalize_url_with(&link.url, &options.canonical);
+        if !url.is_empty() {
+            keys.push(format!("u:{url}"));
+        }
+        if options.match_titles {
+            let title = normalize_title(&link.title);
+            if !title.is_empty() {
+                keys.push(format!("t:{title}"));
+            }
+        }
+        for key in keys {
+            let first = *first_by_key.entry(key).or_insert(i);
+            let (a, b) = (root(&mut parent, first), root(&mut parent, i));
+            // Keep the smallest position as the root.
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for i in 0..n {
+        if feed.links[i].deleted.is_none() {
+            let r = root(&mut parent, i);
+            groups[r].push(i);
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+/// Lowercased alphanumeric words of `title`, joined by single spaces.
+fn normalize_title(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DedupOptions, Keep, MergePolicy, TagMerge, find_duplicates, merge_duplicates};
+    use crate::linkleaf_proto::{DateTime, Feed, Link, Summary, Via};
+
+    fn link(id: &str, title: &str, url: &str, day: i32) -> Link {
+        let dt = DateTime {
+            year: 2025,
+            month: 1,
+            day,
+            ..Default::default()
+        };
+        Link {
+            id: id.into(),
+            title: title.into(),
+            url: url.into(),
+            datetime: Some(dt),
+            created: Some(dt),
+            updated: Some(dt),
+            ..Default::default()
+        }
+    }
+
+    fn feed(links: Vec<Link>) -> Feed {
+        Feed {
+            version: 1,
+            links,
+            ..Default::default()
+        }
+    }
+
+    fn ids(links: &[Link]) -> Vec<&str> {
+        links.iter().map(|l| l.id.as_str()).collect()
+    }
+
+    #[test]
+    fn groups_by_canonical_url_and_optionally_title() {
+        let f = feed(vec![
+            link("a", "Tokio", "https://tokio.rs/", 3),
+            link("b", "Serde", "https://serde.rs/", 2),
+            link("c", "tokio!", "https://TOKIO.rs?utm_source=x", 1),
+            link("d", "Serde", "https://serde.rs/derive", 1),
+        ]);
+
+        let groups = find_duplicates(&f, &DedupOptions::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(ids(&groups[0].links), vec!["a", "c"]);
+
+        let by_title = DedupOptions {
+            match_titles: true,
+            ..Default::default()
+        };
+        let groups = find_duplicates(&f, &by_title);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(ids(&groups[1].links), vec!["b", "d"]);
+    }
+
+    #[test]
+    fn links_without_url_are_not_duplicates_of_each_other() {
+        let mut f = feed(vec![
+            link("a", "A", "", 3),
+            link("b", "B", "  ", 2),
+            link("c", "A", "", 1),
+        ]);
+        assert!(find_duplicates(&f, &DedupOptions::default()).is_empty());
+        assert!(merge_duplicates(&mut f, &DedupOptions::default()).is_empty());
+        assert_eq!(ids(&f.links), vec!["a", "b", "c"]);
+
+        let by_title = DedupOptions {
+            match_titles: true,
+            ..Default::default()
+        };
+        let groups = find_duplicates(&f, &by_title);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(ids(&groups[0].links), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn merge_applies_default_policy() {
+        let mut newer = link("new", "Tokio", "https://tokio.rs/", 5);
+        newer.tags = vec!["rust".into(), "Async".into()];
+        newer.summary = Some(Summary::new("Async runtime for Rust"));
+        let mut older = link("old", "Tokio (old)", "https://tokio.rs", 1);
+        older.tags = vec!["async".into()];
+        older.summary = Some(Summary::new("runtime"));
+        let mut middle = link("mid", "tokio", "https://tokio.rs/#x", 3);
+        middle.via = Some(Via::new("https://news.ycombinator.com/"));
+        let other = link("other", "Serde", "https://serde.rs/", 4);
+        let mut f = feed(vec![newer, other, older, middle]);
+
+        let merged = merge_duplicates(&mut f, &DedupOptions::default());
+        assert_eq!(merged.len(), 1);
+        assert_eq!(ids(&merged[0].group.links), vec!["new", "old", "mid"]);
+
+        // Merged link takes the survivor's place, behind the newer "other".
+        assert_eq!(ids(&f.links), vec!["other", "old"]);
+        let m = &f.links[1];
+        assert_eq!(m.title, "Tokio (old)");
+        assert_eq!(m.created.as_ref().unwrap().day, 1, "oldest kept");
+        assert_eq!(m.updated.as_ref().unwrap().day, 5, "latest update kept");
+        assert_eq!(m.tags, vec!["async", "rust"]);
+        assert_eq!(
+            m.summary.as_ref().unwrap().content,
+            "Async runtime for Rust"
+        );
+        assert_eq!(m.via.as_ref().unwrap().url, "https://news.ycombinator.com/");
+    }
+
+    #[test]
+    fn union_matches_tags_like_the_rest_of_the_crate() {
+        let mut a = link("a", "A", "https://a/", 1);
+        a.tags = vec!["Rust ".into(), "Ünïcode".into()];
+        let mut b = link("b", "B", "https://a", 2);
+        b.tags = vec!["RUST".into(), "ÜNÏCODE".into(), "web".into()];
+        let mut f = feed(vec![a, b]);
+
+        merge_duplicates(&mut f, &DedupOptions::default());
+        assert_eq!(f.links[0].tags, vec!["Rust ", "Ünïcode", "web"]);
+    }
+
+    #[test]
+    fn merge_policy_is_configurable() {
+        let mut a = link("a", "A", "https://a/", 2);
+        a.tags = vec!["x".into()];
+        let mut b = link("b", "B", "https://a", 1);
+        b.tags = vec!["y".into()];
+        let mut f = feed(vec![a, b]);
+
+        let options = DedupOptions {
+            policy: MergePolicy {
+                keep: Keep::Newest,
+                tags: TagMerge::Survivor,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        merge_duplicates(&mut f, &options);
+        assert_eq!(ids(&f.links), vec!["a"]);
+        assert_eq!(f.links[0].tags, vec!["x"]);
+    }
+}
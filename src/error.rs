@@ -0,0 +1,211 @@
+//! Error type returned by every fallible API in this crate.
+//!
+//! [`Error`] implements [`std::error::Error`] with `source()` chains, so it
+//! converts into `anyhow::Error` (or any `Box<dyn Error>`) with `?`.
+
+use crate::fs::RevisionConflict;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Convenience alias used throughout the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while reading, writing or exporting a feed.
+///
+/// Variants that relate to a file carry its `path`; the underlying cause is
+/// available through [`std::error::Error::source`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The feed file does not exist.
+    NotFound {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Any other I/O failure on `path` (permissions, disk full, rename, ...).
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file at `path` is not a valid protobuf `Feed`.
+    Decode {
+        path: PathBuf,
+        source: prost::DecodeError,
+    },
+    /// The feed could not be encoded to protobuf.
+    Encode { source: prost::EncodeError },
+    /// The advisory lock at `path` was still held by someone else after `timeout`.
+    Lock { path: PathBuf, timeout: Duration },
+    /// The advisory lock at `path` was broken as stale while held (see
+    /// [`FeedLock::refresh`](crate::fs::FeedLock::refresh)).
+    LockLost { path: PathBuf },
+    /// The feed at `path` changed since it was read (see
+    /// [`write_feed_if_unchanged`](crate::fs::write_feed_if_unchanged)).
+    Conflict {
+        path: PathBuf,
+        source: RevisionConflict,
+    },
+    /// No link in the feed at `path` matched `selector`.
+    LinkNotFound { path: PathBuf, selector: String },
+    /// Input rejected by validation.
+    Validation(String),
+    /// The local UTC offset could not be determined.
+    TimeOffset(time::error::IndeterminateOffset),
+    /// The feed was written by format `version`, newer than the newest
+    /// version this crate reads (`supported`, see
+    /// [`CURRENT_VERSION`](crate::migrations::CURRENT_VERSION)).
+    UnsupportedVersion { version: u32, supported: u32 },
+    /// A feed could not be rendered to an export format (RSS, Atom).
+    Export {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl Error {
+    /// Build an [`Error::NotFound`] or [`Error::Io`] depending on the I/O error kind.
+    pub(crate) fn io(path: &Path, source: std::io::Error) -> Self {
+        let path = path.to_path_buf();
+        if source.kind() == std::io::ErrorKind::NotFound {
+            Error::NotFound { path, source }
+        } else {
+            Error::Io { path, source }
+        }
+    }
+
+    /// `true` if the feed file does not exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. })
+    }
+
+    /// The file this error relates to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::NotFound { path, .. }
+            | Error::Io { path, .. }
+            | Error::Decode { path, .. }
+            | Error::Lock { path, .. }
+            | Error::LockLost { path }
+            | Error::Conflict { path, .. }
+            | Error::LinkNotFound { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound { path, .. } => {
+                write!(f, "failed to read {}: not found", path.display())
+            }
+            Error::Io { path, .. } => write!(f, "I/O error on {}", path.display()),
+            Error::Decode { path, .. } => {
+                write!(f, "failed to decode protobuf: {}", path.display())
+            }
+            Error::Encode { .. } => write!(f, "failed to encode protobuf Feed"),
+            Error::Lock { path, timeout } => write!(
+                f,
+                "timed out after {timeout:?} waiting for lock {}",
+                path.display()
+            ),
+            Error::LockLost { path } => {
+                write!(f, "lock {} was broken while held", path.display())
+            }
+            Error::Conflict { path, source } => write!(f, "{source}: {}", path.display()),
+            Error::LinkNotFound { path, selector } => {
+                write!(f, "no link matching {selector} in {}", path.display())
+            }
+            Error::Validation(msg) => write!(f, "invalid input: {msg}"),
+            Error::TimeOffset(_) => write!(f, "failed to get local time offset"),
+            Error::UnsupportedVersion { version, supported } => write!(
+                f,
+                "feed format version {version} is newer than the supported version {supported}; upgrade linkleaf to read it"
+            ),
+            Error::Export { .. } => write!(f, "failed to export feed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotFound { source, .. } | Error::Io { source, .. } => Some(source),
+            Error::Decode { source, .. } => Some(source),
+            Error::Encode { source } => Some(source),
+            Error::Conflict { source, .. } => Some(source),
+            Error::TimeOffset(source) => Some(source),
+            Error::Export { source } => Some(source.as_ref()),
+            Error::Lock { .. }
+            | Error::LockLost { .. }
+            | Error::LinkNotFound { .. }
+            | Error::Validation(_)
+            | Error::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+impl From<time::error::IndeterminateOffset> for Error {
+    fn from(e: time::error::IndeterminateOffset) -> Self {
+        Error::TimeOffset(e)
+    }
+}
+
+impl From<rss::Error> for Error {
+    fn from(e: rss::Error) -> Self {
+        Error::Export {
+            source: Box::new(e),
+        }
+    }
+}
+
+impl From<atom_syndication::Error> for Error {
+    fn from(e: atom_syndication::Error) -> Self {
+        Error::Export {
+            source: Box::new(e),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        Error::Export {
+            source: Box::new(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use std::error::Error as _;
+    use std::path::Path;
+
+    #[test]
+    fn io_splits_not_found_from_other_errors() {
+        let nf = Error::io(
+            Path::new("a.pb"),
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        );
+        assert!(nf.is_not_found());
+        assert_eq!(nf.path(), Some(Path::new("a.pb")));
+
+        let denied = Error::io(
+            Path::new("a.pb"),
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+        assert!(matches!(denied, Error::Io { .. }));
+        assert!(denied.source().is_some());
+    }
+
+    #[test]
+    fn converts_into_anyhow() {
+        fn fails() -> anyhow::Result<()> {
+            Err(Error::Validation("empty title".into()))?;
+            Ok(())
+        }
+        let err = fails().unwrap_err();
+        assert!(err.downcast_ref::<Error>().is_some());
+        assert!(err.to_string().contains("empty title"));
+    }
+}
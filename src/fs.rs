@@ -1,14 +1,27 @@
+use crate::error::{Error, Result};
 use crate::linkleaf_proto::Feed;
-use anyhow::{Context, Result};
+use crate::migrations::migrate;
 use prost::Message;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use std::{fs, io::Write};
+use uuid::Uuid;
+
+/// How long [`FeedLock::acquire`] waits for a contended lock by default.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Age after which an abandoned lock file is considered stale by default.
+pub const DEFAULT_LOCK_STALE_AFTER: Duration = Duration::from_secs(60);
 
 /// Read a protobuf feed from disk.
 ///
 /// ## Behavior
 /// - Reads the entire file at `path` into memory.
 /// - Decodes the bytes into a [`Feed`] using `prost`’s `Message::decode`.
+/// - Upgrades feeds of older format versions in memory (see
+///   [`migrations`](crate::migrations)); e.g. version 1 links get `created`/`updated`
+///   backfilled from their `datetime`. The file itself is not rewritten.
 ///
 /// ## Arguments
 /// - `path`: Path to the `.pb` file to read.
@@ -17,30 +30,38 @@ use std::{fs, io::Write};
 /// The decoded [`Feed`] on success.
 ///
 /// ## Errors
-/// - I/O errors from [`fs::read`], wrapped with context
-///   `"failed to read {path}"`.
-/// - Protobuf decode errors from `Feed::decode`, wrapped with context
-///   `"failed to decode protobuf: {path}"`.
-/// - The error type is [`anyhow::Error`] via your crate-wide `Result`.
+/// - [`Error::NotFound`] if the file does not exist.
+/// - [`Error::Io`] for any other error from [`fs::read`].
+/// - [`Error::Decode`] if the bytes are not a valid protobuf `Feed`.
+/// - [`Error::UnsupportedVersion`] if the feed was written by a newer format
+///   version than [`CURRENT_VERSION`](crate::migrations::CURRENT_VERSION).
 ///
 /// ## Example
 /// ```no_run
 /// use std::path::PathBuf;
 /// use linkleaf_core::fs::read_feed;
-/// use anyhow::Result;
+/// use linkleaf_core::Result;
 ///
 /// fn main() -> Result<()> {
 ///     let path = PathBuf::from("mylinks.pb");
 ///     let feed = read_feed(&path)?;
 ///     println!("title: {}, links: {}", feed.title, feed.links.len());
-///     Ok::<(), anyhow::Error>(())
+///     Ok(())
 /// }
 /// ```
 pub fn read_feed<P: AsRef<Path>>(path: P) -> Result<Feed> {
-    let path = path.as_ref();
-    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
-    Feed::decode(bytes.as_slice())
-        .with_context(|| format!("failed to decode protobuf: {}", path.display()))
+    let mut feed = decode_feed(path.as_ref())?;
+    migrate(&mut feed)?;
+    Ok(feed)
+}
+
+/// Read and decode the feed at `path` as stored, without migrating it.
+pub(crate) fn decode_feed(path: &Path) -> Result<Feed> {
+    let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+    Feed::decode(bytes.as_slice()).map_err(|source| Error::Decode {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
 /// Write a protobuf feed to disk **atomically** (best-effort).
@@ -55,7 +76,8 @@ pub fn read_feed<P: AsRef<Path>>(path: P) -> Result<Feed> {
 ///     forwards that error as-is.
 ///
 /// The input `feed` is consumed and returned unchanged on success to make
-/// call sites ergonomic.
+/// call sites ergonomic. In particular `feed.revision` is written as given;
+/// use [`write_feed_if_unchanged`] when the revision should be checked and bumped.
 ///
 /// ## Arguments
 /// - `path`: Destination path of the `.pb` file.
@@ -65,20 +87,17 @@ pub fn read_feed<P: AsRef<Path>>(path: P) -> Result<Feed> {
 /// The same [`Feed`] value that was written (handy for chaining).
 ///
 /// ## Errors
-/// - Directory creation errors from [`fs::create_dir_all`], with context
-///   `"failed to create directory {dir}"`.
-/// - File creation/write/flush errors for the temporary file, with context
-///   `"failed to write {tmp}"`.
-/// - Rename errors when moving the temp file into place, with context
-///   `"failed to move temp file into place: {path}"`.
-/// - Protobuf encode errors from `feed.encode(&mut buf)`.
-/// - The error type is [`anyhow::Error`] via your crate-wide `Result`.
+/// - [`Error::Io`] for directory creation errors (path = the directory).
+/// - [`Error::Io`] for creation/write/flush errors of the temporary file
+///   (path = the temporary file).
+/// - [`Error::Io`] when renaming the temp file into place (path = `path`).
+/// - [`Error::Encode`] for protobuf encode errors.
 ///
 /// ## Example
 /// ```no_run
 /// use std::path::PathBuf;
 /// use linkleaf_core::fs::{read_feed, write_feed};
-/// use anyhow::Result;
+/// use linkleaf_core::Result;
 ///
 /// fn main() -> Result<()> {
 ///     let path = PathBuf::from("mylinks.pb");
@@ -93,38 +112,429 @@ pub fn read_feed<P: AsRef<Path>>(path: P) -> Result<Feed> {
 /// ## Notes
 /// - Atomicity requires the temporary file and the destination to be on the
 ///   **same filesystem**.
-/// - If multiple processes may write concurrently, consider adding a file lock
-///   around the write section.
+/// - `write_feed` does not lock on its own. Read-modify-write cycles across
+///   processes should hold a [`FeedLock`] for the whole cycle.
 pub fn write_feed<P: AsRef<Path>>(path: P, feed: Feed) -> Result<Feed> {
     let path = path.as_ref();
-    // Ensure parent directory exists (if any)
-    if let Some(dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
-        fs::create_dir_all(dir)
-            .with_context(|| format!("failed to create directory {}", dir.display()))?;
-    }
-
     let mut buf = Vec::with_capacity(1024);
     feed.encode(&mut buf)
-        .context("failed to encode protobuf Feed")?;
+        .map_err(|source| Error::Encode { source })?;
+
+    write_atomic(path, &buf)?;
+    Ok(feed)
+}
 
-    let tmp = path.with_extension("pb.tmp");
+/// Write `bytes` to `<path>.tmp`, fsync it and rename it over `path`,
+/// creating parent directories as needed.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    create_parent_dir(path)?;
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
     {
-        let mut f =
-            fs::File::create(&tmp).with_context(|| format!("failed to write {}", tmp.display()))?;
-        f.write_all(&buf)?;
+        let mut f = fs::File::create(&tmp).map_err(|e| Error::io(&tmp, e))?;
+        f.write_all(bytes).map_err(|e| Error::io(&tmp, e))?;
         // Ensure bytes are on disk, not just in the OS page cache
-        f.sync_all()?;
+        f.sync_all().map_err(|e| Error::io(&tmp, e))?;
     }
-    fs::rename(&tmp, &path)
-        .with_context(|| format!("failed to move temp file into place: {}", path.display()))?;
-    Ok(feed)
+    fs::rename(&tmp, path).map_err(|e| Error::io(path, e))
+}
+
+fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+    Ok(())
+}
+
+/// Details of a failed [`write_feed_if_unchanged`]: the feed on disk no longer
+/// has the revision the caller read.
+///
+/// Returned as the source of [`Error::Conflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RevisionConflict {
+    /// Revision the caller expected to find on disk.
+    pub expected: u64,
+    /// Revision actually found on disk.
+    pub actual: u64,
+}
+
+impl std::fmt::Display for RevisionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "feed was modified concurrently: expected revision {}, found {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for RevisionConflict {}
+
+/// Write a feed only if the file still has the revision the caller read.
+///
+/// ## Behavior
+/// - Takes the feed's [`FeedLock`] for the duration of the check and write.
+///   The lock is not re-entrant: while you already hold it (e.g. for a batch),
+///   calling this blocks until [`DEFAULT_LOCK_TIMEOUT`] and fails with
+///   [`Error::Lock`]. Use [`write_feed_if_unchanged_locked`] instead.
+/// - Reads the revision currently stored at `path` (a missing file counts as
+///   revision `0`).
+/// - If it differs from `expected_revision`, nothing is written and
+///   [`Error::Conflict`] is returned.
+/// - Otherwise sets `feed.revision = expected_revision + 1` and persists it
+///   atomically via [`write_feed`].
+///
+/// ## Arguments
+/// - `path`: Destination path of the `.pb` file.
+/// - `feed`: The edited feed to persist (consumed).
+/// - `expected_revision`: The `revision` of the feed as it was originally read.
+///
+/// ## Returns
+/// The written [`Feed`], carrying its new revision.
+///
+/// ## Errors
+/// - [`Error::Conflict`] if another writer changed the file in the meantime.
+/// - Lock, read/decode and write errors as for [`FeedLock::acquire`],
+///   [`read_feed`] and [`write_feed`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::Error;
+/// use linkleaf_core::fs::{read_feed, write_feed_if_unchanged};
+///
+/// let path = "mylinks.pb";
+/// let feed = read_feed(path)?;
+/// let seen = feed.revision;
+/// let mut edited = feed.clone();
+/// edited.title = "Edited".into();
+/// match write_feed_if_unchanged(path, edited, seen) {
+///     Ok(written) => assert_eq!(written.revision, seen + 1),
+///     Err(Error::Conflict { source, .. }) => {
+///         // re-read, merge and retry
+///         eprintln!("{source}");
+///     }
+///     Err(e) => return Err(e),
+/// }
+/// Ok::<(), Error>(())
+/// ```
+pub fn write_feed_if_unchanged<P: AsRef<Path>>(
+    path: P,
+    feed: Feed,
+    expected_revision: u64,
+) -> Result<Feed> {
+    let path = path.as_ref();
+    let lock = FeedLock::acquire(path)?;
+    write_feed_if_unchanged_locked(&lock, path, feed, expected_revision)
+}
+
+/// Like [`write_feed_if_unchanged`], for callers that already hold the feed's
+/// [`FeedLock`]; `lock` is the proof.
+///
+/// ## Errors
+/// - [`Error::Validation`] if `lock` guards a different feed than `path`.
+/// - [`Error::Conflict`] if another writer changed the file in the meantime.
+/// - [`Error::LockLost`] if `lock` was broken as stale, even though the file
+///   is unchanged.
+/// - Read/decode and write errors as for [`write_feed_if_unchanged`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::fs::{FeedLock, read_feed, write_feed_if_unchanged_locked};
+///
+/// let path = "mylinks.pb";
+/// let lock = FeedLock::acquire(path)?;
+/// let mut feed = read_feed(path)?;
+/// let seen = feed.revision;
+/// feed.title = "Edited".into();
+/// write_feed_if_unchanged_locked(&lock, path, feed, seen)?;
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn write_feed_if_unchanged_locked<P: AsRef<Path>>(
+    lock: &FeedLock,
+    path: P,
+    mut feed: Feed,
+    expected_revision: u64,
+) -> Result<Feed> {
+    let path = path.as_ref();
+    if !same_entry(lock.path(), &lock_path(path)) {
+        return Err(Error::Validation(format!(
+            "{} does not guard {}",
+            lock.path().display(),
+            path.display()
+        )));
+    }
+    // A conflict explains more than a lost lock, so it is checked first.
+    let actual = stored_revision(path)?;
+    if actual != expected_revision {
+        #[cfg(feature = "logs")]
+        tracing::warn!(expected = expected_revision, actual, path = %path.display(), "revision conflict");
+        return Err(Error::Conflict {
+            path: path.to_path_buf(),
+            source: RevisionConflict {
+                expected: expected_revision,
+                actual,
+            },
+        });
+    }
+    if !lock.is_held() {
+        return Err(Error::LockLost {
+            path: lock.path().to_path_buf(),
+        });
+    }
+
+    feed.revision = expected_revision + 1;
+    write_feed(path, feed)
+}
+
+/// `a` and `b` name the same directory entry, however their directories are
+/// spelled (`./`, `..`, symlinks).
+fn same_entry(a: &Path, b: &Path) -> bool {
+    fn dir(p: &Path) -> Option<PathBuf> {
+        let parent = p.parent().filter(|d| !d.as_os_str().is_empty());
+        fs::canonicalize(parent.unwrap_or(Path::new("."))).ok()
+    }
+    a == b || (a.file_name() == b.file_name() && dir(a).is_some_and(|d| dir(b) == Some(d)))
+}
+
+fn stored_revision(path: &Path) -> Result<u64> {
+    match fs::read(path) {
+        Ok(bytes) => Feed::decode(bytes.as_slice())
+            .map(|f| f.revision)
+            .map_err(|source| Error::Decode {
+                path: path.to_path_buf(),
+                source,
+            }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(Error::io(path, e)),
+    }
+}
+
+/// Path of the advisory lock file guarding the feed at `path`.
+///
+/// The lock lives next to the feed, with `.lock` appended to its file name,
+/// e.g. `mylinks.pb` → `mylinks.pb.lock`.
+pub fn lock_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut lock = path.as_ref().as_os_str().to_owned();
+    lock.push(".lock");
+    PathBuf::from(lock)
+}
+
+/// Tuning knobs for [`FeedLock::acquire_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockOptions {
+    /// Maximum time to wait for another holder to release the lock.
+    pub timeout: Duration,
+    /// A lock file whose last modification is older than this is assumed to be
+    /// left behind by a crashed process and is broken.
+    pub stale_after: Duration,
+    /// Delay between attempts while the lock is contended.
+    pub retry_interval: Duration,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        LockOptions {
+            timeout: DEFAULT_LOCK_TIMEOUT,
+            stale_after: DEFAULT_LOCK_STALE_AFTER,
+            retry_interval: Duration::from_millis(25),
+        }
+    }
+}
+
+/// Advisory, inter-process lock on a feed file.
+///
+/// ## Behavior
+/// - Acquiring creates [`lock_path`] (`<feed>.lock`) exclusively (`O_CREAT | O_EXCL`) and
+///   writes a token into it: the holder's process id and a random nonce.
+/// - While another holder owns the lock, acquisition retries until
+///   [`LockOptions::timeout`] elapses.
+/// - A lock file older than [`LockOptions::stale_after`] is treated as
+///   abandoned and broken: it is renamed aside, which only one contender can
+///   do, and removed if it is still stale. Long-running holders should call
+///   [`FeedLock::refresh`] to keep their lock from looking stale.
+/// - Dropping the guard removes the lock file, but only while it still holds
+///   this guard's token. A lock that was broken and taken by someone else is
+///   left alone.
+///
+/// The lock is advisory: it only protects against writers that also use it.
+/// [`crate::add`] and the other mutating APIs of this crate take it
+/// automatically; take it yourself when batching several reads and writes.
+/// While holding it, write with [`write_feed`] or
+/// [`write_feed_if_unchanged_locked`]; functions that take the lock
+/// themselves would wait for it to time out.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::fs::{FeedLock, read_feed, write_feed};
+///
+/// let path = "mylinks.pb";
+/// let _lock = FeedLock::acquire(path)?;
+/// let mut feed = read_feed(path)?;
+/// feed.title = "Renamed".into();
+/// write_feed(path, feed)?;
+/// // lock released here
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FeedLock {
+    path: PathBuf,
+    token: String,
+}
+
+impl FeedLock {
+    /// Acquire the lock for the feed at `path` with [`LockOptions::default`].
+    ///
+    /// ## Errors
+    /// - [`Error::Lock`] after [`DEFAULT_LOCK_TIMEOUT`] if another holder keeps the lock.
+    /// - [`Error::Io`] while creating the lock file or its parent directory.
+    pub fn acquire<P: AsRef<Path>>(path: P) -> Result<FeedLock> {
+        Self::acquire_with(path, &LockOptions::default())
+    }
+
+    /// Acquire the lock for the feed at `path` with explicit options.
+    ///
+    /// ## Errors
+    /// - [`Error::Lock`] after `options.timeout` if another holder keeps the lock.
+    /// - [`Error::Io`] while creating the lock file or its parent directory.
+    pub fn acquire_with<P: AsRef<Path>>(path: P, options: &LockOptions) -> Result<FeedLock> {
+        let lock = lock_path(path);
+        create_parent_dir(&lock)?;
+
+        let token = format!("{} {}", std::process::id(), Uuid::new_v4().simple());
+        let started = Instant::now();
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+            {
+                Ok(mut f) => {
+                    // Without the token the lock could not be released safely.
+                    if let Err(e) = writeln!(f, "{token}").and_then(|()| f.sync_all()) {
+                        let _ = fs::remove_file(&lock);
+                        return Err(Error::io(&lock, e));
+                    }
+                    #[cfg(feature = "logs")]
+                    tracing::debug!(path = %lock.display(), "feed lock acquired");
+                    return Ok(FeedLock { path: lock, token });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if is_stale(&lock, options.stale_after) {
+                        #[cfg(feature = "logs")]
+                        tracing::warn!(path = %lock.display(), "breaking stale feed lock");
+                        // Another contender may break it first and take a
+                        // fresh lock; only remove what is still stale.
+                        take_lock_if(&lock, |aside| is_stale(aside, options.stale_after))
+                            .map_err(|e| Error::io(&lock, e))?;
+                        continue;
+                    }
+                    if started.elapsed() >= options.timeout {
+                        return Err(Error::Lock {
+                            path: lock,
+                            timeout: options.timeout,
+                        });
+                    }
+                    thread::sleep(options.retry_interval);
+                }
+                Err(e) => return Err(Error::io(&lock, e)),
+            }
+        }
+    }
+
+    /// Path of the lock file held by this guard.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `true` while the lock file still holds this guard's token, i.e. the
+    /// lock was not broken as stale in the meantime.
+    pub fn is_held(&self) -> bool {
+        holds_token(&self.path, &self.token)
+    }
+
+    /// Bump the lock file's modification time so it is not mistaken for a
+    /// stale lock while a long batch is still running.
+    ///
+    /// ## Errors
+    /// - [`Error::LockLost`] if the lock was broken as stale (and maybe taken
+    ///   by someone else); the guard no longer protects anything.
+    /// - [`Error::Io`] if the lock file cannot be touched.
+    pub fn refresh(&self) -> Result<()> {
+        if !self.is_held() {
+            return Err(Error::LockLost {
+                path: self.path.clone(),
+            });
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .and_then(|f| f.set_modified(SystemTime::now()))
+            .map_err(|e| Error::io(&self.path, e))
+    }
+}
+
+impl Drop for FeedLock {
+    fn drop(&mut self) {
+        let token = &self.token;
+        let _released = take_lock_if(&self.path, |aside| holds_token(aside, token));
+        #[cfg(feature = "logs")]
+        match _released {
+            Ok(true) => tracing::debug!(path = %self.path.display(), "feed lock released"),
+            _ => tracing::warn!(path = %self.path.display(), "feed lock was lost before release"),
+        }
+    }
+}
+
+fn holds_token(lock: &Path, token: &str) -> bool {
+    fs::read_to_string(lock).is_ok_and(|held| held.trim() == token)
+}
+
+/// Atomically move the lock file at `lock` aside and remove it if `pred`
+/// holds for the moved file. Otherwise it is put back, unless a new lock was
+/// created in the meantime.
+///
+/// Returns whether the lock was removed; a missing lock counts as not removed.
+fn take_lock_if(lock: &Path, pred: impl FnOnce(&Path) -> bool) -> std::io::Result<bool> {
+    let mut name = lock.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", Uuid::new_v4().simple()));
+    let aside = lock.with_file_name(name);
+
+    match fs::rename(lock, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    let taken = pred(&aside);
+    if !taken {
+        // `hard_link` never replaces an existing file.
+        let _ = fs::hard_link(&aside, lock);
+    }
+    fs::remove_file(&aside)?;
+    Ok(taken)
+}
+
+fn is_stale(lock: &Path, stale_after: Duration) -> bool {
+    fs::metadata(lock)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age > stale_after)
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{read_feed, write_feed};
-    use crate::linkleaf_proto::Feed;
+    use super::{
+        FeedLock, LockOptions, lock_path, read_feed, write_feed, write_feed_if_unchanged,
+        write_feed_if_unchanged_locked,
+    };
+    use crate::error::Error;
+    use crate::linkleaf_proto::{DateTime, Feed, Link};
+    use crate::migrations::CURRENT_VERSION;
     use anyhow::Result;
+    use std::time::{Duration, SystemTime};
     use std::{fs, path::PathBuf};
     use tempfile::tempdir;
 
@@ -133,6 +543,7 @@ mod tests {
     fn mk_feed(title: &str) -> Feed {
         Feed {
             title: title.to_string(),
+            version: CURRENT_VERSION,
             ..Default::default()
         }
     }
@@ -153,6 +564,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_feed_backfills_created_from_datetime() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let legacy = DateTime {
+            year: 2023,
+            month: 2,
+            day: 3,
+            ..Default::default()
+        };
+        let mut feed = mk_feed("legacy");
+        feed.version = 1; // written before `created`/`updated` existed
+        feed.links.push(Link {
+            id: "1".into(),
+            datetime: Some(legacy),
+            ..Default::default()
+        });
+        write_feed(&path, feed)?;
+
+        let read = read_feed(&path)?;
+        assert_eq!(read.links[0].created, Some(legacy));
+        assert_eq!(read.links[0].updated, Some(legacy));
+        Ok(())
+    }
+
     #[test]
     fn write_feed_creates_parent_dirs() -> Result<()> {
         let dir = tempdir()?;
@@ -193,6 +629,7 @@ mod tests {
         let path = dir.path().join("does_not_exist.pb");
 
         let err = read_feed(&path).unwrap_err();
+        assert!(err.is_not_found());
         let msg = err.to_string();
         assert!(
             msg.contains("failed to read"),
@@ -209,6 +646,7 @@ mod tests {
         fs::write(&path, b"this is not a protobuf")?;
 
         let err = read_feed(&path).unwrap_err();
+        assert!(matches!(err, Error::Decode { .. }));
         let msg = err.to_string();
         assert!(
             msg.contains("failed to decode protobuf:"),
@@ -216,4 +654,199 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn write_if_unchanged_bumps_revision() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+
+        // Missing file counts as revision 0.
+        let first = write_feed_if_unchanged(&path, mk_feed("v1"), 0)?;
+        assert_eq!(first.revision, 1);
+
+        let second = write_feed_if_unchanged(&path, mk_feed("v2"), first.revision)?;
+        assert_eq!(second.revision, 2);
+        assert_eq!(read_feed(&path)?.revision, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn write_if_unchanged_detects_conflict() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let base = write_feed_if_unchanged(&path, mk_feed("base"), 0)?;
+
+        // Another writer gets in first.
+        write_feed_if_unchanged(&path, mk_feed("theirs"), base.revision)?;
+
+        let err = write_feed_if_unchanged(&path, mk_feed("mine"), base.revision).unwrap_err();
+        let Error::Conflict { source, .. } = err else {
+            panic!("expected a conflict, got: {err}");
+        };
+        assert_eq!(source.expected, 1);
+        assert_eq!(source.actual, 2);
+        assert_eq!(read_feed(&path)?.title, "theirs", "file must be untouched");
+        Ok(())
+    }
+
+    #[test]
+    fn write_if_unchanged_locked_works_under_a_held_lock() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        write_feed(&path, mk_feed("v1"))?;
+
+        let lock = FeedLock::acquire(&path)?;
+        let feed = read_feed(&path)?;
+        let seen = feed.revision;
+        let written = write_feed_if_unchanged_locked(&lock, &path, mk_feed("v2"), seen)?;
+        assert_eq!(written.revision, seen + 1);
+        let err = write_feed_if_unchanged_locked(&lock, &path, mk_feed("v3"), seen).unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+
+        let other = dir.path().join("other.pb");
+        let err = write_feed_if_unchanged_locked(&lock, &other, mk_feed("x"), 0).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)), "{err}");
+        assert_eq!(read_feed(&path)?.title, "v2");
+        Ok(())
+    }
+
+    #[test]
+    fn locked_write_accepts_other_spellings_of_the_path() -> Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("sub"))?;
+        let path = dir.path().join("feed.pb");
+        let lock = FeedLock::acquire(dir.path().join("sub/../feed.pb"))?;
+
+        let written = write_feed_if_unchanged_locked(&lock, &path, mk_feed("v1"), 0)?;
+        assert_eq!(written.revision, 1);
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(dir.path(), &link)?;
+            let via_link = link.join("feed.pb");
+            write_feed_if_unchanged_locked(&lock, via_link, mk_feed("v2"), 1)?;
+            assert_eq!(read_feed(&path)?.title, "v2");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn lock_is_exclusive_and_released_on_drop() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let opts = LockOptions {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+
+        let held = FeedLock::acquire_with(&path, &opts)?;
+        assert_eq!(held.path(), lock_path(&path));
+        assert!(lock_path(&path).exists());
+
+        let err = FeedLock::acquire_with(&path, &opts).unwrap_err();
+        assert!(
+            matches!(err, Error::Lock { .. }),
+            "second acquire should time out, got: {err}"
+        );
+
+        drop(held);
+        assert!(!lock_path(&path).exists(), "lock file removed on drop");
+        let _again = FeedLock::acquire_with(&path, &opts)?;
+        Ok(())
+    }
+
+    #[test]
+    fn lock_path_keeps_the_whole_file_name() {
+        assert_eq!(
+            lock_path("dir/mylinks.pb"),
+            PathBuf::from("dir/mylinks.pb.lock")
+        );
+        assert_ne!(lock_path("links.bin"), lock_path("links.dat"));
+        assert_ne!(lock_path("feed"), lock_path("feed.pb"));
+    }
+
+    #[test]
+    fn stale_lock_is_broken() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+
+        // Simulate a lock left behind by a crashed process.
+        let f = fs::File::create(lock_path(&path))?;
+        f.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        drop(f);
+
+        let opts = LockOptions {
+            timeout: Duration::from_millis(50),
+            stale_after: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let _lock = FeedLock::acquire_with(&path, &opts)?;
+        Ok(())
+    }
+
+    #[test]
+    fn drop_leaves_a_lock_taken_over_by_someone_else() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let opts = LockOptions {
+            timeout: Duration::from_millis(50),
+            stale_after: Duration::from_secs(60),
+            ..Default::default()
+        };
+
+        // Our lock goes stale and another process breaks it.
+        let ours = FeedLock::acquire_with(&path, &opts)?;
+        assert!(ours.is_held());
+        let f = fs::OpenOptions::new().write(true).open(ours.path())?;
+        f.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        drop(f);
+        let theirs = FeedLock::acquire_with(&path, &opts)?;
+
+        assert!(!ours.is_held());
+        assert!(matches!(ours.refresh(), Err(Error::LockLost { .. })));
+        drop(ours);
+        assert!(theirs.is_held(), "their lock survives our drop");
+        assert!(FeedLock::acquire_with(&path, &opts).is_err());
+
+        drop(theirs);
+        assert!(!lock_path(&path).exists());
+        let leftovers = fs::read_dir(dir.path())?.count();
+        assert_eq!(leftovers, 0, "no renamed lock files left behind");
+        Ok(())
+    }
+
+    #[test]
+    fn fresh_lock_is_not_taken_by_a_late_breaker() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let held = FeedLock::acquire(&path)?;
+
+        // A contender that saw an older, stale lock re-checks after renaming.
+        assert!(!super::take_lock_if(held.path(), |aside| super::is_stale(
+            aside,
+            Duration::from_secs(60)
+        ))?);
+        assert!(held.is_held(), "fresh lock put back");
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_keeps_lock_fresh() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("feed.pb");
+        let held = FeedLock::acquire(&path)?;
+
+        let f = fs::OpenOptions::new().write(true).open(held.path())?;
+        f.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        held.refresh()?;
+
+        let opts = LockOptions {
+            timeout: Duration::from_millis(50),
+            stale_after: Duration::from_secs(60),
+            ..Default::default()
+        };
+        assert!(FeedLock::acquire_with(&path, &opts).is_err());
+        Ok(())
+    }
 }
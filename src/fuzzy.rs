@@ -0,0 +1,258 @@
+//! Typo-tolerant matching over link titles and summaries.
+//!
+//! Each query word is compared against the words of the title and the
+//! summary with the Damerau–Levenshtein distance (adjacent transpositions
+//! count as one edit). Longer words tolerate more edits: none up to 3
+//! characters, one up to 6, two beyond. A query word may also match the start
+//! of a longer word (`tok` finds `tokio`), at a lower score.
+//!
+//! Every query word must match somewhere. The byte ranges of the matched
+//! words are returned so a UI can highlight them.
+
+use crate::error::Result;
+use crate::fs::read_feed;
+use crate::linkleaf_proto::Link;
+use std::ops::Range;
+use std::path::Path;
+
+const TITLE_WEIGHT: f32 = 2.0;
+const SUMMARY_WEIGHT: f32 = 1.0;
+/// Similarity multiplier when a query word only matches a word's prefix.
+const PREFIX_FACTOR: f32 = 0.8;
+
+/// How well a link matched a fuzzy query, and where.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FuzzyMatch {
+    /// Higher is better. Exact title matches of every query word score highest.
+    pub score: f32,
+    /// Byte ranges of matched words in `Link.title`, sorted and non-overlapping.
+    pub title: Vec<Range<usize>>,
+    /// Byte ranges of matched words in `Link.summary.content`, sorted and
+    /// non-overlapping.
+    pub summary: Vec<Range<usize>>,
+}
+
+/// Match `link` against `query`.
+///
+/// ## Returns
+/// `None` if some query word matches no word of the title or summary, or if
+/// `query` has no words.
+pub fn fuzzy_match(link: &Link, query: &str) -> Option<FuzzyMatch> {
+    let terms: Vec<Vec<char>> = words(query).map(|(_, w)| w).collect();
+    if terms.is_empty() {
+        return None;
+    }
+    let title: Vec<_> = words(&link.title).collect();
+    let summary: Vec<_> = link
+        .summary
+        .as_ref()
+        .map(|s| words(&s.content).collect())
+        .unwrap_or_default();
+
+    let mut m = FuzzyMatch::default();
+    for term in &terms {
+        let in_title = best_word(term, &title);
+        let in_summary = best_word(term, &summary);
+        let t = in_title.as_ref().map_or(0.0, |(_, s)| s * TITLE_WEIGHT);
+        let s = in_summary.as_ref().map_or(0.0, |(_, s)| s * SUMMARY_WEIGHT);
+        if in_title.is_none() && in_summary.is_none() {
+            return None;
+        }
+        m.score += t.max(s);
+        m.title.extend(in_title.map(|(r, _)| r));
+        m.summary.extend(in_summary.map(|(r, _)| r));
+    }
+    normalize_ranges(&mut m.title);
+    normalize_ranges(&mut m.summary);
+    Some(m)
+}
+
+/// Fuzzy-search the feed at `file` for `query`.
+///
+/// ## Returns
+/// Matching links (trash excluded) with their [`FuzzyMatch`], best first. Ties
+/// keep the feed's stored order.
+///
+/// ## Errors
+/// Any error from [`read_feed`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::fuzzy_search;
+///
+/// for (link, m) in fuzzy_search("mylinks.pb", "tokoi runtme")? {
+///     let marked: Vec<&str> = m.title.iter().map(|r| &link.title[r.clone()]).collect();
+///     println!("{:.2} {} {:?}", m.score, link.title, marked);
+/// }
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn fuzzy_search<P: AsRef<Path>>(file: P, query: &str) -> Result<Vec<(Link, FuzzyMatch)>> {
+    let feed = read_feed(file)?;
+    let mut hits: Vec<(Link, FuzzyMatch)> = feed
+        .links
+        .into_iter()
+        .filter(|l| l.deleted.is_none())
+        .filter_map(|l| fuzzy_match(&l, query).map(|m| (l, m)))
+        .collect();
+    // Stable sort: equal scores keep stored order.
+    hits.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+    Ok(hits)
+}
+
+/// Edits tolerated for a query word of `len` characters.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// The word most similar to `term`, with its similarity in `0.0..=1.0`.
+fn best_word(term: &[char], words: &[(Range<usize>, Vec<char>)]) -> Option<(Range<usize>, f32)> {
+    let max = max_edits(term.len());
+    let mut best: Option<(Range<usize>, f32)> = None;
+    for (range, word) in words {
+        let full = edit_distance(term, word, max)
+            .map(|d| 1.0 - d as f32 / term.len().max(word.len()) as f32);
+        let prefix = (word.len() > term.len())
+            .then(|| edit_distance(term, &word[..term.len()], max))
+            .flatten()
+            .map(|d| (1.0 - d as f32 / term.len() as f32) * PREFIX_FACTOR);
+        let Some(sim) = full.into_iter().chain(prefix).reduce(f32::max) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(_, s)| sim > *s) {
+            best = Some((range.clone(), sim));
+        }
+    }
+    best
+}
+
+/// Optimal-string-alignment distance between `a` and `b`, or `None` if it
+/// exceeds `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let w = b.len() + 1;
+    // Three rows: i-2, i-1, i.
+    let mut rows = vec![vec![0usize; w]; 3];
+    for (j, cell) in rows[1].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        let (cur, prev, prev2) = (i % 3, (i + 2) % 3, (i + 1) % 3);
+        rows[cur][0] = i;
+        let mut row_min = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (rows[prev][j] + 1)
+                .min(rows[cur][j - 1] + 1)
+                .min(rows[prev][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[prev2][j - 2] + 1);
+            }
+            rows[cur][j] = d;
+            row_min = row_min.min(d);
+        }
+        if row_min > max {
+            return None;
+        }
+    }
+    let d = rows[a.len() % 3][b.len()];
+    (d <= max).then_some(d)
+}
+
+/// Alphanumeric runs of `text` with their byte range, lowercased.
+fn words(text: &str) -> impl Iterator<Item = (Range<usize>, Vec<char>)> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(move |w| {
+            let start = w.as_ptr() as usize - text.as_ptr() as usize;
+            (
+                start..start + w.len(),
+                w.chars().flat_map(char::to_lowercase).collect(),
+            )
+        })
+}
+
+fn normalize_ranges(ranges: &mut Vec<Range<usize>>) {
+    ranges.sort_by_key(|r| r.start);
+    ranges.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, fuzzy_match, fuzzy_search};
+    use crate::fs::write_feed;
+    use crate::linkleaf_proto::{DateTime, Feed, Link, Summary};
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    fn link(id: &str, title: &str, summary: &str) -> Link {
+        Link {
+            id: id.into(),
+            title: title.into(),
+            url: format!("https://example.com/{id}"),
+            summary: Some(Summary::new(summary)),
+            datetime: Some(DateTime::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_transpositions_once() {
+        let c = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&c("tokio"), &c("tokio"), 2), Some(0));
+        assert_eq!(edit_distance(&c("tokoi"), &c("tokio"), 2), Some(1));
+        assert_eq!(edit_distance(&c("runtme"), &c("runtime"), 2), Some(1));
+        assert_eq!(edit_distance(&c("kitten"), &c("sitting"), 2), None);
+    }
+
+    #[test]
+    fn tolerates_typos_and_reports_highlights() {
+        let l = link("a", "The Tokio Runtime", "Async I/O for Rust");
+
+        let m = fuzzy_match(&l, "tokoi runtme").expect("typos are tolerated");
+        assert_eq!(m.title, vec![4..9, 10..17]);
+        assert_eq!(&l.title[m.title[0].clone()], "Tokio");
+        assert!(m.summary.is_empty());
+
+        let m = fuzzy_match(&l, "rsut").expect("matches the summary");
+        assert_eq!(
+            &l.summary.as_ref().unwrap().content[m.summary[0].clone()],
+            "Rust"
+        );
+
+        // Every word must match; short words must match exactly.
+        assert!(fuzzy_match(&l, "tokio python").is_none());
+        assert!(fuzzy_match(&l, "thr").is_none());
+        assert!(fuzzy_match(&l, "").is_none());
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_closer_and_title_matches_first() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let mut trashed = link("d", "Tokio", "");
+        trashed.deleted = Some(DateTime::default());
+        let feed = Feed {
+            version: 1,
+            links: vec![
+                link("a", "Notes", "all about tokio"),
+                link("b", "Tokyo travel", ""),
+                link("c", "Tokio", ""),
+                trashed,
+            ],
+            ..Default::default()
+        };
+        write_feed(&file, feed)?;
+
+        let hits = fuzzy_search(&file, "tokio")?;
+        let ids: Vec<_> = hits.iter().map(|(l, _)| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+        assert!(hits[0].1.score > hits[1].1.score);
+        Ok(())
+    }
+}
@@ -1,103 +1,72 @@
+pub mod atom;
+pub mod canonical;
+pub mod check;
+pub mod clock;
+mod datetime;
+pub mod dedup;
+pub mod error;
 pub mod fs;
+pub mod fuzzy;
+pub mod migrations;
+pub mod query;
+pub mod search;
+pub mod store;
+pub mod tags;
 pub mod validation;
 pub mod linkleaf_proto {
     include!(concat!(env!("OUT_DIR"), "/linkleaf.v1.rs"));
 }
 
-use crate::fs::{read_feed, write_feed};
+pub use crate::atom::{AtomOptions, feed_to_atom_xml};
+pub use crate::error::{Error, Result};
+pub use crate::fuzzy::fuzzy_search;
+pub use crate::query::Query;
+pub use crate::search::search;
+pub use crate::store::{AddOptions, FeedStore, LinkPatch, LinkSelector, TrashOptions};
+
+use crate::dedup::{DedupOptions, MergedGroup};
+use crate::fs::read_feed;
 use crate::linkleaf_proto::{DateTime, Feed, Link, Summary, Via};
-use anyhow::Result;
-use chrono::{FixedOffset, TimeZone};
+use chrono::FixedOffset;
 use rss::{CategoryBuilder, ChannelBuilder, GuidBuilder, Item, ItemBuilder};
+use std::collections::BTreeMap;
 use std::path::Path;
-use time::Month;
-use time::OffsetDateTime;
+use std::sync::Arc;
 use uuid::Uuid;
 
-fn is_not_found(err: &anyhow::Error) -> bool {
-    err.downcast_ref::<std::io::Error>()
-        .map(|e| e.kind() == std::io::ErrorKind::NotFound)
-        .unwrap_or(false)
-}
-
-fn update_link_in_place(
-    feed: &mut Feed,
-    pos: usize,
-    title: String,
-    url: String,
-    date: Option<DateTime>,
-    summary: Option<Summary>,
-    tags: Vec<String>,
-    via: Option<Via>,
-) -> Link {
-    // take ownership, mutate, then reinsert at front
-    let mut item = feed.links.remove(pos);
-    item.title = title;
-    item.url = url;
-    item.datetime = date;
-    item.summary = summary;
-    item.tags = tags;
-    item.via = via;
-
-    feed.links.insert(0, item.clone());
-    item
-}
-
-fn insert_new_link_front(
-    feed: &mut Feed,
-    id: String,
-    title: String,
-    url: String,
-    datetime: Option<DateTime>,
-    summary: Option<Summary>,
-    tags: Vec<String>,
-    via: Option<Via>,
-) -> Link {
-    let link = Link {
-        summary: summary,
-        tags, // field init shorthand
-        via: via,
-        id,
-        title,
-        url,
-        datetime,
-    };
-    feed.links.insert(0, link.clone());
-    link
-}
-
-fn from_month(value: Month) -> i32 {
-    match value {
-        Month::January => 1,
-        Month::February => 2,
-        Month::March => 3,
-        Month::April => 4,
-        Month::May => 5,
-        Month::June => 6,
-        Month::July => 7,
-        Month::August => 8,
-        Month::September => 9,
-        Month::October => 10,
-        Month::November => 11,
-        Month::December => 12,
-    }
-}
-
 /// Add or update a link in a protobuf feed file, then persist the feed.
 ///
 /// ## Behavior
-/// - Reads the feed at `file`. If it doesn't exist, a new feed is initialized (`version = 1`).
+/// - Opens the feed through a [`FeedStore`], which holds the feed's
+///   [`FeedLock`](crate::fs::FeedLock) for the whole read-modify-write cycle,
+///   so concurrent `add` calls from other processes are serialized.
+/// - Reads the feed at `file`. If it doesn't exist, a new feed is initialized
+///   (`version =` [`migrations::CURRENT_VERSION`]).
 /// - If an `id` is provided:
 ///   - Updates the existing link with that `id` if found (title, url, summary, tags, via),
-///     sets its `date` to **today (local datetime, `YYYY-MM-DD HH:MM:SS`)**, and moves it
+///     sets its `updated` timestamp to **now (local datetime)**, and moves it
 ///     to the **front** (newest-first).
 ///   - Otherwise inserts a **new** link at the front with that explicit `id`.
 /// - If no `id` is provided:
-///   - Updates the first link whose `url` matches; sets `date` to today and moves it to the front.
+///   - Updates the first link whose URL is the same once canonicalized (see
+///     [`canonical`]; e.g. `https://Tokio.rs/?utm_source=x` matches
+///     `https://tokio.rs`); sets `updated` to now and moves it to the front.
+///     The link's `url` is set to `url` exactly as given.
 ///   - Otherwise inserts a **new** link at the front with a freshly generated UUID v4 `id`.
+/// - Tags are normalized with [`tags::TagPolicy::default`]: lowercased,
+///   whitespace collapsed, duplicates dropped (`["Rust", "rust "]` → `["rust"]`).
+///   Set [`AddOptions::tags`] to change that.
+/// - New links get `created`, `updated` and `datetime` set to now (local time,
+///   or UTC if the local offset is unknown; see [`clock::SystemClock`] and
+///   [`AddOptions::clock`]). Updates keep
+///   `created` and `datetime`. Use [`add_with_options`] to update without moving
+///   the link to the front.
 ///
 /// Persists the entire feed by calling `write_feed`, which writes atomically
-/// via a temporary file and `rename`.
+/// via a temporary file and `rename`. The feed's `revision` is incremented.
+///
+/// Each call re-reads and re-writes the whole file; for bulk imports open a
+/// [`FeedStore`] once and call [`FeedStore::upsert`] repeatedly instead.
 ///
 /// ## Arguments
 /// - `file`: Path to the `.pb` feed file to update/create.
@@ -112,12 +81,17 @@ fn from_month(value: Month) -> i32 {
 /// The newly created or updated [`Link`].
 ///
 /// ## Ordering
-/// Links are kept **newest-first**; both inserts and updates end up at index `0`.
+/// Links are kept **newest-first**; both inserts and updates end up at index `0`
+/// (see [`AddOptions::move_to_front`]).
 ///
 /// ## Errors
-/// - Propagates any error from `read_feed` (except “not found”, which initializes a new feed).
+/// - [`Error::Validation`] if `title` or `url` is empty. Use
+///   [`add_with_options`] with [`validation::Strictness::Strict`] to also
+///   check URL syntax, lengths and tags (see [`validation::validate_link`]).
+/// - [`Error::Lock`] if the feed lock cannot be acquired within
+///   [`fs::DEFAULT_LOCK_TIMEOUT`].
+/// - Propagates any error from `read_feed` (except [`Error::NotFound`], which initializes a new feed).
 /// - Propagates any error from `write_feed`.
-/// - No inter-process locking is performed; concurrent writers may race.
 ///
 /// ## Example
 /// ```no_run
@@ -146,20 +120,23 @@ fn from_month(value: Month) -> i32 {
 ///     "Tokio • Async Rust",
 ///     "https://tokio.rs/",
 ///     Some(Summary::new("A runtime for reliable async apps")),
-///     [],                 // no tags change
+///     [],                 // replaces the tags: this clears them (see `update`)
 ///     None,
 ///     Some(_id),          // provide id -> update or insert with that id
 /// )?;
 ///
 /// assert_eq!(a2.id, a.id);
-/// Ok::<(), anyhow::Error>(())
+/// Ok::<(), Box<dyn std::error::Error>>(())
 /// // After update, the item is at the front (index 0).
 /// ```
 ///
 /// ## Notes
 /// - Providing an `id` gives the item a stable identity; updates by `id` will also update
 ///   the stored `url` to the new value you pass.
-/// - `date` is always set to “today” in local time on both create and update.
+/// - Updates overwrite **every** field, including `tags`, `summary` and `via`. To change
+///   only some fields, use [`update`] with a [`LinkPatch`].
+/// - `created`/`datetime` are set once, when the link is first added; `updated` is
+///   refreshed on every update.
 pub fn add<P, S, T>(
     file: P,
     title: S,
@@ -174,330 +151,969 @@ where
     S: Into<String>,
     T: IntoIterator<Item = S>,
 {
-    let file = file.as_ref();
-    // compute local timestamp once
-    let local_now = OffsetDateTime::now_local()
-        .map_err(|e| anyhow::anyhow!("failed to get local time offset: {e}"))?;
-
-    let datetime = DateTime {
-        year: local_now.year() as i32,
-        month: from_month(local_now.month()),
-        day: local_now.day() as i32,
-        hours: local_now.hour() as i32,
-        minutes: local_now.minute() as i32,
-        seconds: local_now.second() as i32,
-        nanos: local_now.nanosecond() as i32,
-    };
-
-    // read or init feed
-    let mut feed = match read_feed(file) {
-        Ok(f) => f,
-        Err(err) if is_not_found(&err) => {
-            let mut f = Feed::default();
-            f.version = 1;
-            f
-        }
-        Err(err) => return Err(err),
-    };
-
-    let title = title.into();
-    let url = url.into();
-    let summary = summary.map(Into::into);
-    let via = via.map(Into::into);
-    let tags: Vec<String> = tags.into_iter().map(Into::into).collect();
-    let id_opt: Option<String> = id.map(|u| u.to_string());
-
-    // behavior:
-    // - If `id` provided: update by id; else insert (even if URL duplicates).
-    // - If no `id`: update by URL; else insert with fresh UUID.
-    let updated_or_new = match id_opt {
-        Some(uid) => {
-            if let Some(pos) = feed.links.iter().position(|l| l.id == uid) {
-                let item = update_link_in_place(
-                    &mut feed,
-                    pos,
-                    title,
-                    url,
-                    Some(datetime),
-                    summary,
-                    tags,
-                    via,
-                );
-                #[cfg(feature = "logs")]
-                tracing::info!(id = %item.id, "updated existing link by id");
-                item
-            } else {
-                let item = insert_new_link_front(
-                    &mut feed,
-                    uid,
-                    title,
-                    url,
-                    Some(datetime),
-                    summary,
-                    tags,
-                    via,
-                );
-                #[cfg(feature = "logs")]
-                tracing::info!(id = %item.id, "inserted new link with explicit id");
-                item
-            }
-        }
-        None => {
-            if let Some(pos) = feed.links.iter().position(|l| l.url == url) {
-                let item = update_link_in_place(
-                    &mut feed,
-                    pos,
-                    title,
-                    url,
-                    Some(datetime),
-                    summary,
-                    tags,
-                    via,
-                );
-                #[cfg(feature = "logs")]
-                tracing::info!(id = %item.id, "inserted new link with explicit id");
-                item
-            } else {
-                let uid = Uuid::new_v4().to_string();
-                let item = insert_new_link_front(
-                    &mut feed,
-                    uid,
-                    title,
-                    url,
-                    Some(datetime),
-                    summary,
-                    tags,
-                    via,
-                );
-                #[cfg(feature = "logs")]
-                tracing::info!(id = %item.id, "inserted new link with explicit id");
-                item
-            }
-        }
-    };
-
-    let _modified_feed = write_feed(&file, feed)?;
-    #[cfg(feature = "logs")]
-    tracing::debug!(links = _modified_feed.links.len(), path = %file.display(), "feed written");
+    add_with_options(
+        file,
+        title,
+        url,
+        summary,
+        tags,
+        via,
+        id,
+        &AddOptions::default(),
+    )
+}
 
-    Ok(updated_or_new)
+/// Add or update a link like [`add`], with explicit [`AddOptions`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{AddOptions, add_with_options};
+///
+/// // Update by URL but leave the link where it is in the feed.
+/// let options = AddOptions { move_to_front: false, ..Default::default() };
+/// add_with_options("mylinks.pb", "Tokio", "https://tokio.rs/", None, ["rust"], None, None, &options)?;
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn add_with_options<P, S, T>(
+    file: P,
+    title: S,
+    url: S,
+    summary: Option<Summary>,
+    tags: T,
+    via: Option<Via>,
+    id: Option<Uuid>,
+    options: &AddOptions,
+) -> Result<Link>
+where
+    P: AsRef<Path>,
+    S: Into<String>,
+    T: IntoIterator<Item = S>,
+{
+    let mut store = FeedStore::open_for_edit(file)?;
+    let link = store.upsert_with(title, url, summary, tags, via, id, options)?;
+    store.commit()?;
+    Ok(link)
 }
 
-/// Read and return the feed stored in a protobuf file.
+/// Remove links from a protobuf feed file, then persist the feed.
 ///
 /// ## Behavior
-/// Calls [`read_feed`] on the provided path and returns the parsed [`Feed`]. If tags and/or
-/// date filters are provided it filters the resulting [`Feed`].
+/// - Opens the feed through a [`FeedStore`] (holding its lock), removes every
+///   link matched by `selector` and commits atomically.
+/// - If nothing matches, the file is left untouched.
 ///
 /// ## Arguments
 /// - `file`: Path to the `.pb` feed file.
+/// - `selector`: Which links to remove: [`LinkSelector::Id`], [`LinkSelector::Url`]
+///   (exact match) or [`LinkSelector::Predicate`].
 ///
 /// ## Returns
-/// The parsed [`Feed`] on success.
+/// The removed [`Link`]s, in their stored (newest-first) order.
 ///
 /// ## Errors
-/// Any error bubbled up from [`read_feed`], e.g. I/O errors (file missing,
-/// permissions), or decode errors if the file is not a valid feed.
+/// - [`Error::LinkNotFound`] if no link matched `selector` (including when the
+///   file does not exist yet).
+/// - [`Error::Lock`], decode and I/O errors from opening and committing the store.
 ///
 /// ## Example
 /// ```no_run
-/// use std::path::PathBuf;
-/// use linkleaf_core::*;
+/// use linkleaf_core::{Error, LinkSelector, remove};
 ///
-/// let path = PathBuf::from("mylinks.pb");
-/// let feed = list(&path, None, None)?;
-/// println!("Title: {}, links: {}", feed.title, feed.links.len());
-/// Ok::<(), anyhow::Error>(())
+/// match remove("mylinks.pb", LinkSelector::url("https://tokio.rs/")) {
+///     Ok(removed) => println!("removed {} link(s)", removed.len()),
+///     Err(Error::LinkNotFound { .. }) => println!("nothing to remove"),
+///     Err(e) => return Err(e),
+/// }
+/// Ok::<(), Error>(())
 /// ```
-pub fn list<P: AsRef<Path>>(
-    file: P,
-    tags: Option<Vec<String>>,
-    datetime: Option<DateTime>,
-) -> Result<Feed> {
-    let file = file.as_ref();
-    let mut feed = read_feed(file)?;
-
-    let tag_norms: Option<Vec<String>> = tags.map(|ts| {
-        ts.iter()
-            .map(|t| t.trim().to_ascii_lowercase())
-            .filter(|t| !t.is_empty())
-            .collect()
-    });
-
-    let date_filter: Option<&DateTime> = datetime.as_ref();
-
-    feed.links.retain(|l| {
-        let tag_ok = match &tag_norms {
-            Some(needles) => l
-                .tags
-                .iter()
-                .any(|t| needles.iter().any(|n| t.eq_ignore_ascii_case(n))),
-            None => true,
-        };
-
-        let date_ok = match date_filter {
-            Some(p) => l.datetime.as_ref().map(|dt| dt == p).unwrap_or(false),
-            None => true,
-        };
+pub fn remove<P: AsRef<Path>>(file: P, selector: LinkSelector) -> Result<Vec<Link>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let removed = store.remove_where(&selector);
+    if removed.is_empty() {
+        return Err(Error::LinkNotFound {
+            path: store.path().to_path_buf(),
+            selector: selector.to_string(),
+        });
+    }
+    store.commit()?;
+    #[cfg(feature = "logs")]
+    tracing::info!(count = removed.len(), %selector, "removed links");
+    Ok(removed)
+}
 
-        tag_ok && date_ok
-    });
+/// Partially update a link in a protobuf feed file, then persist the feed.
+///
+/// ## Behavior
+/// - Applies `patch` to the link with the given `id`; fields the patch leaves
+///   unset keep their current value (see [`LinkPatch`]).
+/// - The link's `updated` timestamp is set to the current local time (or the
+///   time of [`LinkPatch::clock`]); `created` and `datetime` are kept.
+/// - By default the link moves to the front. With
+///   [`LinkPatch::preserve_position`] set, it keeps its position.
+///
+/// ## Returns
+/// The updated [`Link`].
+///
+/// ## Errors
+/// - [`Error::LinkNotFound`] if no link has this `id`.
+/// - [`Error::Validation`] if the patched link breaks
+///   [`LinkPatch::validation`]; the file is left untouched.
+/// - [`Error::Lock`], decode and I/O errors.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{LinkPatch, update};
+///
+/// // Fix a typo without touching tags, summary, position or date.
+/// let id = "3f1c2a9e-0000-4000-8000-000000000000";
+/// let patch = LinkPatch::new().title("Tokio - Asynchronous Rust").preserve_position(true);
+/// let link = update("mylinks.pb", id, &patch)?;
+/// assert_eq!(link.title, "Tokio - Asynchronous Rust");
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn update<P: AsRef<Path>>(file: P, id: &str, patch: &LinkPatch) -> Result<Link> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let link = store.update(id, patch)?;
+    store.commit()?;
+    Ok(link)
+}
 
-    Ok(feed)
+/// Move links to the trash, then persist the feed.
+///
+/// ## Behavior
+/// - Stamps the `deleted` tombstone of every matched link that is not already
+///   trashed with the current local time (see [`trash_with`] to choose the
+///   clock). Links stay in the file.
+/// - Trashed links are hidden from [`list`] and [`feed_to_rss_xml`] unless
+///   requested with [`ListOptions::include_trashed`] / [`RssOptions::include_trashed`].
+///
+/// ## Returns
+/// The newly trashed [`Link`]s.
+///
+/// ## Errors
+/// - [`Error::LinkNotFound`] if no untrashed link matched `selector`.
+/// - [`Error::Lock`], decode and I/O errors.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{LinkSelector, restore, trash};
+///
+/// trash("mylinks.pb", LinkSelector::url("https://tokio.rs/"))?;
+/// // changed our mind
+/// restore("mylinks.pb", LinkSelector::url("https://tokio.rs/"))?;
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn trash<P: AsRef<Path>>(file: P, selector: LinkSelector) -> Result<Vec<Link>> {
+    trash_with(file, selector, &TrashOptions::default())
 }
 
-impl DateTime {
-    /// Converts this `DateTime` to an RFC 2822 string.
-    ///
-    /// Returns `None` if any field is invalid (e.g., month > 12, day > 31).
-    #[allow(deprecated)]
-    pub fn to_rfc2822(&self) -> Option<String> {
-        // Convert i32 fields to u32 safely
-        let month = u32::try_from(self.month).ok()?; // 1..=12
-        let day = u32::try_from(self.day).ok()?; // 1..=31
-        let hours = u32::try_from(self.hours).ok()?; // 0..=23
-        let minutes = u32::try_from(self.minutes).ok()?; // 0..=59
-        let seconds = u32::try_from(self.seconds).ok()?; // 0..=60 for leap seconds
-
-        let dt = FixedOffset::east_opt(0) // UTC;
-            .map(|d| {
-                d.ymd(self.year, month, day)
-                    .and_hms(hours, minutes, seconds)
-            })?;
+/// Move links to the trash like [`trash`], with explicit [`TrashOptions`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::clock::UtcClock;
+/// use linkleaf_core::{LinkSelector, TrashOptions, trash_with};
+/// use std::sync::Arc;
+///
+/// let options = TrashOptions { clock: Some(Arc::new(UtcClock)) };
+/// trash_with("mylinks.pb", LinkSelector::url("https://tokio.rs/"), &options)?;
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn trash_with<P: AsRef<Path>>(
+    file: P,
+    selector: LinkSelector,
+    options: &TrashOptions,
+) -> Result<Vec<Link>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    if let Some(clock) = &options.clock {
+        store = store.with_clock(Arc::clone(clock));
+    }
+    let trashed = store.trash_where(&selector);
+    if trashed.is_empty() {
+        return Err(Error::LinkNotFound {
+            path: store.path().to_path_buf(),
+            selector: selector.to_string(),
+        });
+    }
+    store.commit()?;
+    Ok(trashed)
+}
 
-        Some(dt.to_rfc2822())
+/// Restore trashed links, then persist the feed.
+///
+/// ## Returns
+/// The restored [`Link`]s, with their tombstone cleared.
+///
+/// ## Errors
+/// - [`Error::LinkNotFound`] if no trashed link matched `selector`.
+/// - [`Error::Lock`], decode and I/O errors.
+pub fn restore<P: AsRef<Path>>(file: P, selector: LinkSelector) -> Result<Vec<Link>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let restored = store.restore_where(&selector);
+    if restored.is_empty() {
+        return Err(Error::LinkNotFound {
+            path: store.path().to_path_buf(),
+            selector: selector.to_string(),
+        });
     }
+    store.commit()?;
+    Ok(restored)
 }
 
-fn to_datetime(proto_datetime: &Option<DateTime>) -> Option<String> {
-    proto_datetime.as_ref().and_then(|dt| dt.to_rfc2822())
+/// Permanently delete trashed links, then persist the feed.
+///
+/// ## Behavior
+/// Removes every link whose `deleted` tombstone is strictly older than
+/// `older_than`. Untrashed links are never touched. If nothing qualifies the
+/// file is left untouched.
+///
+/// ## Returns
+/// The purged [`Link`]s (possibly empty).
+///
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
+pub fn purge_trash<P: AsRef<Path>>(file: P, older_than: &DateTime) -> Result<Vec<Link>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let purged = store.purge_trash(older_than);
+    store.commit()?;
+    Ok(purged)
 }
 
-/// Converts a `Feed` into an RSS 2.0 XML string.
+/// Merge duplicate links in a protobuf feed file, then persist the feed.
 ///
-/// This function generates a fully formatted RSS feed from the provided `Feed`
-/// data structure. Each `Link` in the feed is converted to an `Item` in the
-/// RSS feed using `link_to_rss_item`.
+/// ## Behavior
+/// Groups links by canonical URL (and, if [`DedupOptions::match_titles`] is
+/// set, by normalized title), merges each group according to
+/// [`DedupOptions::policy`] and commits. If there are no duplicates the file
+/// is left untouched. Use [`dedup::find_duplicates`] to only report them.
 ///
-/// # Parameters
+/// ## Returns
+/// The merged groups.
 ///
-/// - `feed`: A reference to a `Feed` struct containing the feed title and links.
-/// - `site_title`: A fallback title for the feed if `feed.title` is empty.
-/// - `site_link`: The canonical URL of the website or feed source; used as the
-///   `<link>` of the channel.
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
 ///
-/// # Returns
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::dedup::DedupOptions;
+/// use linkleaf_core::merge_duplicates;
 ///
-/// Returns a `Result<String>` containing the RSS XML string if successful.
+/// for m in merge_duplicates("mylinks.pb", &DedupOptions::default())? {
+///     println!("{} <- {} link(s)", m.merged.url, m.group.links.len());
+/// }
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn merge_duplicates<P: AsRef<Path>>(
+    file: P,
+    options: &DedupOptions,
+) -> Result<Vec<MergedGroup>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let merged = store.merge_duplicates(options);
+    store.commit()?;
+    Ok(merged)
+}
+
+/// Rename a tag on every link of a protobuf feed file, then persist the feed.
 ///
-/// # Errors
+/// ## Behavior
+/// Tags are matched folded (case and whitespace insensitive, see [`tags`]); a
+/// link that already carries `to` just loses `from`. Links keep their
+/// position and `updated` timestamp. If no link carries `from` the file is
+/// left untouched.
 ///
-/// Returns an error if:
-/// - The channel cannot be serialized into XML.
-/// - The resulting UTF-8 string cannot be created from the XML buffer.
+/// ## Returns
+/// The ids of the changed links.
 ///
-/// # Behavior
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
 ///
-/// - If `feed.title` is empty, `site_title` is used as the RSS channel title.
-/// - Each link's tags, summary, guid, and publication date are included in
-///   the corresponding RSS `<item>`.
-/// - The XML is pretty-printed with an indentation of 2 spaces.
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::rename_tag;
 ///
-/// # Example
+/// let changed = rename_tag("mylinks.pb", "k8s", "kubernetes")?;
+/// println!("retagged {} link(s)", changed.len());
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn rename_tag<P: AsRef<Path>>(file: P, from: &str, to: &str) -> Result<Vec<String>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let changed = store.rename_tag(from, to);
+    store.commit()?;
+    Ok(changed)
+}
+
+/// Merge several tags into one on every link of a protobuf feed file, then
+/// persist the feed. Behaves like [`rename_tag`] for each tag in `from`.
 ///
-/// ```rust
-/// use linkleaf_core::linkleaf_proto::Feed;
-/// use linkleaf_core::feed_to_rss_xml;
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
+pub fn merge_tags<P: AsRef<Path>, S: AsRef<str>>(
+    file: P,
+    from: &[S],
+    into: &str,
+) -> Result<Vec<String>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let changed = store.merge_tags(from, into);
+    store.commit()?;
+    Ok(changed)
+}
+
+/// Remove a tag from every link of a protobuf feed file, then persist the
+/// feed. Matching and persistence work like [`rename_tag`].
 ///
-/// let feed = Feed {
-///     title: "My Links".to_string(),
-///     links: vec![/* ... */],
-///     version: 1
-/// };
-/// let rss_xml = feed_to_rss_xml(&feed, "Default Site", "https://example.com")
-///     .expect("Failed to generate RSS XML");
-/// println!("{}", rss_xml);
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
+pub fn delete_tag<P: AsRef<Path>>(file: P, tag: &str) -> Result<Vec<String>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let changed = store.delete_tag(tag);
+    store.commit()?;
+    Ok(changed)
+}
+
+/// Define a tag alias in a protobuf feed file, then persist the feed.
+///
+/// ## Behavior
+/// Stores `alias` → `tag` in the feed's alias table (see
+/// [`tags::define_tag_alias`]). From then on tag filters, [`tags::tag_counts`]
+/// and the tag normalization of [`add`] and [`update`] treat `alias` as `tag`.
+/// Existing link tags are not rewritten. Redefining an alias replaces it.
+///
+/// ## Errors
+/// - [`Error::Validation`] if `alias` or `tag` is empty, or `tag` resolves to
+///   `alias` itself.
+/// - [`Error::Lock`], decode and I/O errors.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{Query, define_tag_alias, list};
+///
+/// define_tag_alias("mylinks.pb", "k8s", "kubernetes")?;
+/// // Also lists links tagged `kubernetes`.
+/// let feed = list("mylinks.pb", Query::any_tag(["k8s"]))?;
+/// # let _ = feed;
+/// Ok::<(), linkleaf_core::Error>(())
 /// ```
-pub fn feed_to_rss_xml(feed: &Feed, site_title: &str, site_link: &str) -> Result<String> {
-    let items: Vec<Item> = feed.links.iter().map(|l| link_to_rss_item(l)).collect();
-    let description = format!("Feed about {} generated through Linkleaf", &feed.title);
+pub fn define_tag_alias<P: AsRef<Path>>(file: P, alias: &str, tag: &str) -> Result<()> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    store.define_tag_alias(alias, tag)?;
+    store.commit()
+}
 
-    let channel = ChannelBuilder::default()
-        .title(if feed.title.is_empty() {
-            site_title.to_string()
-        } else {
-            feed.title.clone()
-        })
-        .link(site_link.to_string())
-        .description(description) // if you have it; else set a default
-        .items(items)
-        .build();
+/// Remove a tag alias from a protobuf feed file, then persist the feed.
+///
+/// ## Returns
+/// The alias's target, or `None` (and the file untouched) if it was not
+/// defined.
+///
+/// ## Errors
+/// [`Error::Lock`], decode and I/O errors.
+pub fn remove_tag_alias<P: AsRef<Path>>(file: P, alias: &str) -> Result<Option<String>> {
+    let mut store = FeedStore::open_for_edit(file)?;
+    let removed = store.remove_tag_alias(alias);
+    store.commit()?;
+    Ok(removed)
+}
 
-    let mut buf = Vec::new();
-    channel.pretty_write_to(&mut buf, b' ', 2)?;
-    Ok(String::from_utf8(buf)?)
+/// Filters, sort order and paging for [`list_with`] and [`list_page`]. All
+/// filters that are set must match.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// Keep links carrying any of these tags, or a tag below one of them
+    /// (`lang` keeps `lang/rust`); case-insensitive, aliases resolved.
+    pub tags: Option<Vec<String>>,
+    /// Keep links whose `datetime` equals this value.
+    pub datetime: Option<DateTime>,
+    /// Keep links matching this [`Query`].
+    pub query: Option<Query>,
+    /// Also return links that are in the trash.
+    pub include_trashed: bool,
+    /// Order of the returned links.
+    pub sort: SortKey,
+    /// Resume after this [`Page::next_cursor`]. The cursor records the sort
+    /// key of the last link on the page, so paging continues at the next link
+    /// even if that one was removed, trashed or filtered out in between. A
+    /// plain link id is accepted too, but only while that link is listed.
+    pub cursor: Option<String>,
+    /// Skip this many links (after `cursor`, if any).
+    pub offset: usize,
+    /// Return at most this many links.
+    pub limit: Option<usize>,
 }
 
-fn link_to_rss_item(l: &Link) -> Item {
-    let cats = l
-        .tags
-        .iter()
-        .map(|t| CategoryBuilder::default().name(t.clone()).build())
-        .collect::<Vec<_>>();
+/// Sort order for [`list_with`] and [`list_page`].
+///
+/// Ties are broken by link id, so every order is total and cursors stay stable.
+/// [`SortKey::Stored`] has no key of its own: if a cursor's link is gone, paging
+/// resumes at its old position, which shifts when links are added to the
+/// front between pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Stored (newest-first) order.
+    #[default]
+    Stored,
+    /// Oldest `created` first.
+    DatetimeAsc,
+    /// Newest `created` first.
+    DatetimeDesc,
+    /// Title, case-insensitive.
+    Title,
+    /// URL as stored.
+    Url,
+    /// Host part of the URL, case-insensitive, then URL.
+    Domain,
+}
 
-    ItemBuilder::default()
-        .title(Some(l.title.clone()))
-        .link(Some(l.url.clone()))
-        .description(l.summary.as_ref().map(|c| c.content.clone()))
-        .categories(cats)
-        .guid(Some(
-            GuidBuilder::default()
-                .value(format!("urn:uuid:{}", l.id))
-                .permalink(false)
-                .build(),
-        ))
-        .pub_date(to_datetime(&l.datetime))
-        .build()
+/// One page of links returned by [`list_page`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Page {
+    /// The links on this page, in the requested order.
+    pub links: Vec<Link>,
+    /// Number of links matching the filters, across all pages.
+    pub total: usize,
+    /// Pass as [`ListOptions::cursor`] to fetch the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
 }
 
-impl Summary {
-    /// Creates a new `Summary` instance with the given content.
-    ///
-    /// # Parameters
-    ///
-    /// - `content`: A string slice containing the summary text. Can be empty.
-    ///
-    /// # Returns
-    ///
-    /// A `Summary` instance containing the provided content.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use linkleaf_core::linkleaf_proto::Summary;
-    ///
-    /// let summary = Summary::new("This is a brief description of the link.");
-    /// assert_eq!(summary.content, "This is a brief description of the link.");
-    /// ```
-    pub fn new(content: &str) -> Self {
-        Summary {
-            content: content.into(),
+impl From<Query> for ListOptions {
+    fn from(query: Query) -> Self {
+        ListOptions {
+            query: Some(query),
+            ..Default::default()
         }
     }
 }
 
-impl Via {
-    /// Creates a new `Via` instance with the given URL.
-    ///
-    /// # Parameters
-    ///
-    /// - `url`: A string slice containing the URL where the link was originally shared.
-    ///
-    /// # Returns
-    ///
-    /// A `Via` instance containing the provided URL.
-    ///
-    /// # Example
+impl From<Option<Query>> for ListOptions {
+    /// `None` lists every link that is not in the trash.
+    fn from(query: Option<Query>) -> Self {
+        ListOptions {
+            query,
+            ..Default::default()
+        }
+    }
+}
+
+/// Read and return the feed stored in a protobuf file.
+///
+/// ## Behavior
+/// Calls [`read_feed`] on the provided path and returns the parsed [`Feed`],
+/// keeping the links selected by `filter`: a [`Query`], `None` for every
+/// link, or full [`ListOptions`] (tags, sort, paging, ...). Trashed links are
+/// left out unless [`ListOptions::include_trashed`] is set.
+///
+/// ## Arguments
+/// - `file`: Path to the `.pb` feed file.
+/// - `filter`: Anything convertible into [`ListOptions`].
+///
+/// ## Returns
+/// The parsed [`Feed`] on success.
+///
+/// ## Errors
+/// Any error bubbled up from [`read_feed`]: [`Error::NotFound`] if the file is
+/// missing, [`Error::Io`] for other I/O failures, or [`Error::Decode`] if the
+/// file is not a valid feed.
+///
+/// ## Example
+/// ```no_run
+/// use std::path::PathBuf;
+/// use linkleaf_core::*;
+///
+/// use linkleaf_core::validation::parse_date;
+///
+/// let path = PathBuf::from("mylinks.pb");
+/// let feed = list(&path, None)?;
+/// println!("Title: {}, links: {}", feed.title, feed.links.len());
+///
+/// let recent_rust = list(&path, Query::any_tag(["rust"]).and(Query::after(parse_date("2025-01-01")?)))?;
+/// Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn list<P: AsRef<Path>, F: Into<ListOptions>>(file: P, filter: F) -> Result<Feed> {
+    list_with(file, &filter.into())
+}
+
+/// Read a feed and filter it according to `options`.
+///
+/// Same as [`list`], with the filters passed as a [`ListOptions`] reference. Sort
+/// and paging options are applied too; use [`list_page`] to also get the
+/// total count and the next cursor.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{ListOptions, Query, list_with};
+/// use linkleaf_core::validation::parse_date;
+///
+/// let everything = list_with(
+///     "mylinks.pb",
+///     &ListOptions { include_trashed: true, ..Default::default() },
+/// )?;
+/// let trashed = everything.links.iter().filter(|l| l.deleted.is_some()).count();
+/// println!("{trashed} link(s) in the trash");
+///
+/// let since = parse_date("2025-01-01").map_err(linkleaf_core::Error::Validation)?;
+/// let recent_rust = list_with(
+///     "mylinks.pb",
+///     &Query::all_tags(["rust"]).and(Query::after(since)).into(),
+/// )?;
+/// println!("{} recent rust link(s)", recent_rust.links.len());
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn list_with<P: AsRef<Path>>(file: P, options: &ListOptions) -> Result<Feed> {
+    let file = file.as_ref();
+    let mut feed = read_feed(file)?;
+    let links = std::mem::take(&mut feed.links);
+    feed.links = select_page(file, links, &feed.tag_aliases, options)?.links;
+    Ok(feed)
+}
+
+/// Read a feed and return one sorted, paginated [`Page`] of its links.
+///
+/// ## Behavior
+/// Filters like [`list_with`], sorts by [`ListOptions::sort`], then skips past
+/// [`ListOptions::cursor`] and [`ListOptions::offset`] and keeps at most
+/// [`ListOptions::limit`] links.
+///
+/// ## Returns
+/// The [`Page`], with the total number of matching links and, if more links
+/// follow, the cursor for the next page.
+///
+/// ## Errors
+/// - [`Error::Validation`] if the cursor was made for another [`SortKey`] or
+///   is malformed.
+/// - [`Error::LinkNotFound`] if the cursor is a plain link id that is no
+///   longer in the filtered result.
+/// - Any error from [`read_feed`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::{ListOptions, SortKey, list_page};
+///
+/// let mut options = ListOptions {
+///     sort: SortKey::Title,
+///     limit: Some(50),
+///     ..Default::default()
+/// };
+/// loop {
+///     let page = list_page("mylinks.pb", &options)?;
+///     for link in &page.links {
+///         println!("{}", link.title);
+///     }
+///     match page.next_cursor {
+///         Some(cursor) => options.cursor = Some(cursor),
+///         None => break,
+///     }
+/// }
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn list_page<P: AsRef<Path>>(file: P, options: &ListOptions) -> Result<Page> {
+    let file = file.as_ref();
+    let feed = read_feed(file)?;
+    select_page(file, feed.links, &feed.tag_aliases, options)
+}
+
+fn select_page(
+    file: &Path,
+    links: Vec<Link>,
+    aliases: &BTreeMap<String, String>,
+    options: &ListOptions,
+) -> Result<Page> {
+    let tag_norms = options
+        .tags
+        .as_deref()
+        .map(|t| normalize_tag_filter(t, aliases));
+    // Keys are computed before filtering: `Stored` keys are feed positions.
+    let mut keyed: Vec<(Vec<SortField>, Link)> = links
+        .into_iter()
+        .enumerate()
+        .map(|(i, l)| (sort_key(&l, options.sort, i), l))
+        .filter(|(_, l)| {
+            (options.include_trashed || l.deleted.is_none())
+                && link_matches(l, tag_norms.as_deref(), aliases, options.datetime.as_ref())
+                && options
+                    .query
+                    .as_ref()
+                    .is_none_or(|q| q.matches_with_aliases(l, aliases))
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let total = keyed.len();
+    let start = match &options.cursor {
+        Some(cursor) => match decode_cursor(cursor, options.sort)? {
+            Some(after) => resume_after(&keyed, options.sort, &after),
+            None => match keyed.iter().position(|(_, l)| &l.id == cursor) {
+                Some(i) => i + 1,
+                None => {
+                    return Err(Error::LinkNotFound {
+                        path: file.to_path_buf(),
+                        selector: format!("cursor {cursor}"),
+                    });
+                }
+            },
+        },
+        None => 0,
+    };
+    let start = start.saturating_add(options.offset).min(total);
+    let end = options
+        .limit
+        .map_or(total, |n| start.saturating_add(n).min(total));
+
+    let next_cursor =
+        (end < total && end > start).then(|| encode_cursor(options.sort, &keyed[end - 1].0));
+    keyed.truncate(end);
+    let links = keyed.drain(start..).map(|(_, l)| l).collect();
+
+    Ok(Page {
+        links,
+        total,
+        next_cursor,
+    })
+}
+
+/// Index of the first link that sorts after the cursor key `after`.
+///
+/// Stored positions shift when links are removed, so a `Stored` cursor
+/// resumes after its link while that is still listed, and otherwise at the
+/// link that now holds its position.
+fn resume_after(keyed: &[(Vec<SortField>, Link)], sort: SortKey, after: &[SortField]) -> usize {
+    if sort == SortKey::Stored {
+        if let Some(SortField::Str(id)) = after.last()
+            && let Some(i) = keyed.iter().position(|(_, l)| &l.id == id)
+        {
+            return i + 1;
+        }
+        return keyed.partition_point(|(k, _)| k[..1] < after[..1.min(after.len())]);
+    }
+    keyed.partition_point(|(k, _)| k.as_slice() <= after)
+}
+
+/// One component of a link's position in a [`SortKey`] order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortField {
+    Int(i64),
+    Str(String),
+}
+
+/// The key `l` sorts by under `key`; `index` is its position in the feed.
+/// Every key ends with the id.
+fn sort_key(l: &Link, key: SortKey, index: usize) -> Vec<SortField> {
+    use SortField::{Int, Str};
+    let created = || {
+        l.created
+            .as_ref()
+            .or(l.datetime.as_ref())
+            .map(store::instant_key)
+    };
+    let id = || Str(l.id.clone());
+    match key {
+        SortKey::Stored => vec![Int(index as i64), id()],
+        // Links without a timestamp come first.
+        SortKey::DatetimeAsc => match created() {
+            Some((secs, nanos)) => vec![Int(1), Int(secs), Int(nanos.into()), id()],
+            None => vec![Int(0), Int(0), Int(0), id()],
+        },
+        // Newest first; links without a timestamp come last.
+        SortKey::DatetimeDesc => match created() {
+            Some((secs, nanos)) => vec![Int(0), Int(-secs), Int(-i64::from(nanos)), id()],
+            None => vec![Int(1), Int(0), Int(0), id()],
+        },
+        SortKey::Title => vec![Str(l.title.to_lowercase()), id()],
+        SortKey::Url => vec![Str(l.url.clone()), id()],
+        SortKey::Domain => vec![
+            Str(url_host(&l.url).to_ascii_lowercase()),
+            Str(l.url.clone()),
+            id(),
+        ],
+    }
+}
+
+const CURSOR_PREFIX: &str = "c1";
+
+fn sort_code(key: SortKey) -> &'static str {
+    match key {
+        SortKey::Stored => "st",
+        SortKey::DatetimeAsc => "da",
+        SortKey::DatetimeDesc => "dd",
+        SortKey::Title => "ti",
+        SortKey::Url => "ur",
+        SortKey::Domain => "do",
+    }
+}
+
+/// `c1.<sort>.<field>...`, with integers as `i<n>` and strings as `s<hex>`.
+fn encode_cursor(sort: SortKey, key: &[SortField]) -> String {
+    let mut cursor = format!("{CURSOR_PREFIX}.{}", sort_code(sort));
+    for field in key {
+        match field {
+            SortField::Int(n) => cursor.push_str(&format!(".i{n}")),
+            SortField::Str(s) => {
+                cursor.push_str(".s");
+                for b in s.bytes() {
+                    cursor.push_str(&format!("{b:02x}"));
+                }
+            }
+        }
+    }
+    cursor
+}
+
+/// The key encoded in `cursor`, or `None` for a plain link id.
+fn decode_cursor(cursor: &str, sort: SortKey) -> Result<Option<Vec<SortField>>> {
+    let Some(rest) = cursor
+        .strip_prefix(CURSOR_PREFIX)
+        .and_then(|r| r.strip_prefix('.'))
+    else {
+        return Ok(None);
+    };
+    let invalid = || Error::Validation(format!("invalid cursor {cursor:?}"));
+    let mut parts = rest.split('.');
+    if parts.next() != Some(sort_code(sort)) {
+        return Err(Error::Validation(format!(
+            "cursor {cursor:?} was not made for {sort:?} order"
+        )));
+    }
+    parts
+        .map(|part| match part.split_at_checked(1) {
+            Some(("i", n)) => n.parse().map(SortField::Int).map_err(|_| invalid()),
+            Some(("s", hex)) if hex.len() % 2 == 0 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<std::result::Result<Vec<u8>, _>>()
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .map(SortField::Str)
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Host part of `url`: between the scheme and the first `/`, `?` or `#`,
+/// without userinfo or port. Returns `""` for URLs without an authority.
+fn url_host(url: &str) -> &str {
+    canonical::split_url(url).map_or("", |parts| parts.host)
+}
+
+/// Resolve aliases in and fold tag needles, dropping empty ones.
+fn normalize_tag_filter(tags: &[String], aliases: &BTreeMap<String, String>) -> Vec<String> {
+    tags.iter()
+        .map(|t| tags::fold_tag(&tags::resolve_tag(aliases, t)))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Legacy filter behind [`list`]'s `tags` / `datetime` arguments: any-of tag match
+/// (folded, hierarchical and alias-resolving, see [`tags::tag_matches`]) and exact
+/// `datetime` match.
+fn link_matches(
+    l: &Link,
+    tag_norms: Option<&[String]>,
+    aliases: &BTreeMap<String, String>,
+    datetime: Option<&DateTime>,
+) -> bool {
+    let tag_ok = match tag_norms {
+        Some(needles) => l.tags.iter().any(|t| {
+            let t = tags::resolve_tag(aliases, t);
+            needles.iter().any(|n| tags::tag_matches(&t, n))
+        }),
+        None => true,
+    };
+
+    let date_ok = match datetime {
+        Some(p) => l.datetime.as_ref().map(|dt| dt == p).unwrap_or(false),
+        None => true,
+    };
+
+    tag_ok && date_ok
+}
+
+impl DateTime {
+    /// This value anchored at `seconds` east of UTC (replacing any offset).
+    /// The civil fields are kept as they are.
+    ///
+    /// ## Example
+    /// ```
+    /// use linkleaf_core::linkleaf_proto::DateTime;
+    ///
+    /// let dt = DateTime::default().with_utc_offset(2 * 3600);
+    /// assert_eq!(dt.utc_offset, Some(7200));
+    /// assert_eq!(DateTime::default().utc_offset, None);
+    /// ```
+    pub fn with_utc_offset(mut self, seconds: i32) -> Self {
+        self.utc_offset = Some(seconds);
+        self
+    }
+
+    /// Converts this `DateTime` to an RFC 2822 string.
+    ///
+    /// The offset is taken from `utc_offset`; values without one are written
+    /// as UTC (`+0000`). A leap second is written as second `60`.
+    ///
+    /// Returns `None` if any field is invalid (e.g., month > 12, February 30,
+    /// an offset of a day or more).
+    pub fn to_rfc2822(&self) -> Option<String> {
+        chrono::DateTime::<FixedOffset>::try_from(self)
+            .ok()
+            .map(|dt| dt.to_rfc2822())
+    }
+}
+
+fn to_datetime(proto_datetime: Option<&DateTime>) -> Option<String> {
+    proto_datetime.and_then(|dt| dt.to_rfc2822())
+}
+
+/// Converts a `Feed` into an RSS 2.0 XML string.
+///
+/// This function generates a fully formatted RSS feed from the provided `Feed`
+/// data structure. Each `Link` in the feed is converted to an `Item` in the
+/// RSS feed using `link_to_rss_item`.
+///
+/// # Parameters
+///
+/// - `feed`: A reference to a `Feed` struct containing the feed title and links.
+/// - `site_title`: A fallback title for the feed if `feed.title` is empty.
+/// - `site_link`: The canonical URL of the website or feed source; used as the
+///   `<link>` of the channel.
+///
+/// # Returns
+///
+/// Returns a `Result<String>` containing the RSS XML string if successful.
+///
+/// # Errors
+///
+/// Returns [`Error::Export`] if:
+/// - The channel cannot be serialized into XML.
+/// - The resulting UTF-8 string cannot be created from the XML buffer.
+///
+/// # Behavior
+///
+/// - If `feed.title` is empty, `site_title` is used as the RSS channel title.
+/// - Trashed links are skipped (see [`feed_to_rss_xml_with_options`]).
+/// - Each link's tags, summary, guid, and publication date are included in
+///   the corresponding RSS `<item>`.
+/// - The XML is pretty-printed with an indentation of 2 spaces.
+///
+/// # Example
+///
+/// ```rust
+/// use linkleaf_core::linkleaf_proto::Feed;
+/// use linkleaf_core::feed_to_rss_xml;
+///
+/// let feed = Feed {
+///     title: "My Links".to_string(),
+///     links: vec![/* ... */],
+///     version: 1,
+///     ..Default::default()
+/// };
+/// let rss_xml = feed_to_rss_xml(&feed, "Default Site", "https://example.com")
+///     .expect("Failed to generate RSS XML");
+/// println!("{}", rss_xml);
+/// ```
+pub fn feed_to_rss_xml(feed: &Feed, site_title: &str, site_link: &str) -> Result<String> {
+    feed_to_rss_xml_with_options(feed, site_title, site_link, &RssOptions::default())
+}
+
+/// Options for [`feed_to_rss_xml_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RssOptions {
+    /// Also export links that are in the trash.
+    pub include_trashed: bool,
+}
+
+/// Converts a `Feed` into an RSS 2.0 XML string, with explicit [`RssOptions`].
+///
+/// Behaves like [`feed_to_rss_xml`]; see it for parameters and errors.
+pub fn feed_to_rss_xml_with_options(
+    feed: &Feed,
+    site_title: &str,
+    site_link: &str,
+    options: &RssOptions,
+) -> Result<String> {
+    let items: Vec<Item> = feed
+        .links
+        .iter()
+        .filter(|l| options.include_trashed || l.deleted.is_none())
+        .map(link_to_rss_item)
+        .collect();
+    let description = format!("Feed about {} generated through Linkleaf", &feed.title);
+
+    let channel = ChannelBuilder::default()
+        .title(if feed.title.is_empty() {
+            site_title.to_string()
+        } else {
+            feed.title.clone()
+        })
+        .link(site_link.to_string())
+        .description(description) // if you have it; else set a default
+        .items(items)
+        .build();
+
+    let mut buf = Vec::new();
+    channel.pretty_write_to(&mut buf, b' ', 2)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+fn link_to_rss_item(l: &Link) -> Item {
+    let cats = l
+        .tags
+        .iter()
+        .map(|t| CategoryBuilder::default().name(t.clone()).build())
+        .collect::<Vec<_>>();
+
+    ItemBuilder::default()
+        .title(Some(l.title.clone()))
+        .link(Some(l.url.clone()))
+        .description(l.summary.as_ref().map(|c| c.content.clone()))
+        .categories(cats)
+        .guid(Some(
+            GuidBuilder::default()
+                .value(format!("urn:uuid:{}", l.id))
+                .permalink(false)
+                .build(),
+        ))
+        .pub_date(to_datetime(l.created.as_ref().or(l.datetime.as_ref())))
+        .build()
+}
+
+impl Summary {
+    /// Creates a new `Summary` instance with the given content.
+    ///
+    /// # Parameters
+    ///
+    /// - `content`: A string slice containing the summary text. Can be empty.
+    ///
+    /// # Returns
+    ///
+    /// A `Summary` instance containing the provided content.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use linkleaf_core::linkleaf_proto::Summary;
+    ///
+    /// let summary = Summary::new("This is a brief description of the link.");
+    /// assert_eq!(summary.content, "This is a brief description of the link.");
+    /// ```
+    pub fn new(content: &str) -> Self {
+        Summary {
+            content: content.into(),
+        }
+    }
+}
+
+impl Via {
+    /// Creates a new `Via` instance with the given URL.
+    ///
+    /// # Parameters
+    ///
+    /// - `url`: A string slice containing the URL where the link was originally shared.
+    ///
+    /// # Returns
+    ///
+    /// A `Via` instance containing the provided URL.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// use linkleaf_core::linkleaf_proto::Via;
@@ -512,10 +1128,21 @@ impl Via {
 
 #[cfg(test)]
 mod tests {
-    use super::{add, feed_to_rss_xml, link_to_rss_item, list};
-    use crate::fs::{read_feed, write_feed};
+    use super::{
+        AddOptions, Error, LinkPatch, LinkSelector, ListOptions, Query, RssOptions, SortKey,
+        TrashOptions, add, add_with_options, define_tag_alias, delete_tag, feed_to_rss_xml,
+        feed_to_rss_xml_with_options, link_to_rss_item, list, list_page, list_with,
+        merge_duplicates, merge_tags, purge_trash, remove, remove_tag_alias, rename_tag, restore,
+        trash, trash_with, update, url_host,
+    };
+    use crate::clock::FixedClock;
+    use crate::dedup::DedupOptions;
+    use crate::fs::{FeedLock, lock_path, read_feed, write_feed};
     use crate::linkleaf_proto::{DateTime, Feed, Link, Summary, Via};
+    use crate::tags::TagPolicy;
+    use crate::validation::{LinkRules, Strictness};
     use anyhow::Result;
+    use std::sync::Arc;
     use tempfile::tempdir;
     use uuid::Uuid;
 
@@ -542,14 +1169,16 @@ mod tests {
             summary: _summary,
             tags: tags.iter().map(|s| s.to_string()).collect(),
             via: _via,
+            ..Default::default()
         }
     }
 
     fn mk_feed(links: Vec<Link>) -> Feed {
-        let mut f = Feed::default();
-        f.version = 1;
-        f.links = links;
-        f
+        Feed {
+            version: 1,
+            links,
+            ..Default::default()
+        }
     }
 
     fn sample_link() -> Link {
@@ -568,7 +1197,9 @@ mod tests {
                 minutes: 30,
                 seconds: 45,
                 nanos: 00,
+                utc_offset: None,
             }),
+            ..Default::default()
         }
     }
 
@@ -577,206 +1208,754 @@ mod tests {
             title: "Test Feed".to_string(),
             links: vec![sample_link()],
             version: 1,
+            ..Default::default()
         }
     }
 
-    // ---- tests ---------------------------------------------------------------
+    // ---- tests ---------------------------------------------------------------
+
+    #[test]
+    fn add_creates_file_and_initializes_feed() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        // via=None & tags string -> defaults + parse_tags used internally
+        let created = add(
+            file.clone(),
+            "Tokio",
+            "https://tokio.rs/",
+            None, // summary -> ""
+            vec!["rust", "async", "tokio"],
+            None,         // via -> ""
+            None::<Uuid>, // id -> generated
+        )?;
+
+        // File exists and can be read; version initialized to 1
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.version, crate::migrations::CURRENT_VERSION);
+        assert_eq!(feed.revision, 1);
+        assert_eq!(feed.links.len(), 1);
+        let l = &feed.links[0];
+        assert_eq!(l.id, created.id);
+        assert_eq!(l.title, "Tokio");
+        assert_eq!(l.url, "https://tokio.rs/");
+        assert_eq!(l.summary, None);
+        assert_eq!(l.via, None);
+        assert_eq!(l.tags, vec!["rust", "async", "tokio"]);
+
+        // ID is a valid UUID
+        let _ = Uuid::parse_str(&created.id).expect("id should be a valid UUID");
+        Ok(())
+    }
+
+    #[test]
+    fn add_with_explicit_id_inserts_with_given_id() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let wanted = Uuid::new_v4();
+
+        let created = add(
+            file.clone(),
+            "A",
+            "https://a.example/",
+            Some(Summary::new("hi")),
+            Some("x,y"),
+            Some(Via::new("via")),
+            Some(wanted),
+        )?;
+
+        assert_eq!(created.id, wanted.to_string());
+
+        // list(None) returns everything; first item is the one we just added
+        let feed = list(&file, None)?;
+        assert_eq!(feed.links.len(), 1);
+        assert_eq!(feed.links[0].id, wanted.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn add_update_by_id_moves_to_front_and_updates_fields() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let tags = ["alpha"];
+        // Seed with two links
+        let a = add(
+            file.clone(),
+            "First",
+            "https://one/",
+            None,
+            tags,
+            None,
+            None::<Uuid>,
+        )?;
+        let _b = add(
+            file.clone(),
+            "Second",
+            "https://two/",
+            None,
+            Some("beta"),
+            None,
+            None,
+        )?;
+
+        // Update by id of 'a': title/url/tags/via/summary overwritten, item moves to front
+        let updated = add(
+            file.clone(),
+            "First (updated)",
+            "https://one-new/",
+            Some(Summary::new("note")),
+            ["rust", "updated"],
+            Some(Via::new("HN")),
+            Some(Uuid::parse_str(&a.id)?),
+        )?;
+        assert_eq!(updated.id, a.id);
+        assert_eq!(updated.title, "First (updated)");
+        assert_eq!(updated.url, "https://one-new/");
+        assert_eq!(updated.summary, Some(Summary::new("note")));
+        assert_eq!(updated.via, Some(Via::new("HN")));
+        assert_eq!(updated.tags, vec!["rust", "updated"]);
+        assert_eq!(updated.created, a.created, "creation time is preserved");
+        assert_eq!(updated.datetime, a.datetime);
+
+        let feed = list(&file, None)?;
+        assert_eq!(feed.links.len(), 2);
+        assert_eq!(feed.links[0].id, a.id, "updated item should be at index 0");
+        assert_eq!(feed.links[0].title, "First (updated)");
+        Ok(())
+    }
+
+    #[test]
+    fn add_update_by_url_when_id_absent() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let first = add(
+            file.clone(),
+            "Original",
+            "https://same.url/",
+            None,
+            None,
+            None,
+            None,
+        )?;
+
+        // Same URL, id=None => update-in-place (but moved to front) and id stays the same
+        let updated = add(
+            file.clone(),
+            "Original (updated)",
+            "https://same.url/",
+            Some(Summary::new("s")),
+            ["t1", "t2"],
+            None,
+            None,
+        )?;
+        assert_eq!(updated.id, first.id);
+
+        let feed = list(&file, None)?;
+        assert_eq!(feed.links.len(), 1);
+        assert_eq!(feed.links[0].title, "Original (updated)");
+        assert_eq!(feed.links[0].tags, vec!["t1", "t2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn add_matches_urls_by_canonical_form() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let a = add(
+            file.clone(),
+            "Tokio",
+            "https://tokio.rs/",
+            None,
+            None,
+            None,
+            None,
+        )?;
+        let a2 = add(
+            file.clone(),
+            "Tokio",
+            "https://TOKIO.rs/?utm_source=x",
+            None,
+            None,
+            None,
+            None,
+        )?;
+        assert_eq!(a2.id, a.id, "same link behind a different spelling");
+        assert_eq!(
+            a2.url, "https://TOKIO.rs/?utm_source=x",
+            "url kept as given"
+        );
+        assert_eq!(read_feed(&file)?.links.len(), 1);
+
+        // Exact matching can still be requested.
+        let exact = AddOptions {
+            canonical: None,
+            ..Default::default()
+        };
+        let b = add_with_options(
+            file.clone(),
+            "Tokio",
+            "https://tokio.rs",
+            None,
+            None,
+            None,
+            None,
+            &exact,
+        )?;
+        assert_ne!(b.id, a.id);
+        Ok(())
+    }
+
+    #[test]
+    fn add_enforces_validation_policy() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let err = add(
+            file.clone(),
+            " ",
+            "https://tokio.rs/",
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        assert!(!file.exists(), "nothing written on validation failure");
+
+        let strict = AddOptions {
+            validation: LinkRules {
+                strictness: Strictness::Strict,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = add_with_options(
+            file.clone(),
+            "Tokio",
+            "tokio.rs",
+            None,
+            None,
+            None,
+            None,
+            &strict,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing scheme"), "{err}");
+
+        // The default only requires a title and a URL.
+        let link = add(file.clone(), "Tokio", "tokio.rs", None, None, None, None)?;
+        assert_eq!(link.url, "tokio.rs");
+        Ok(())
+    }
+
+    #[test]
+    fn add_normalizes_tags_and_tag_ops_persist() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let a = add(
+            file.clone(),
+            "A",
+            "https://a/",
+            None,
+            ["Rust", "rust ", "Machine  Learning"],
+            None,
+            None,
+        )?;
+        assert_eq!(a.tags, vec!["rust", "machine learning"]);
+
+        let as_given = AddOptions {
+            tags: TagPolicy::preserve(),
+            ..Default::default()
+        };
+        let b = add_with_options(
+            file.clone(),
+            "B",
+            "https://b/",
+            None,
+            ["K8s", "Rust"],
+            None,
+            None,
+            &as_given,
+        )?;
+        assert_eq!(b.tags, vec!["K8s", "Rust"]);
+
+        assert_eq!(rename_tag(&file, "k8s", "kubernetes")?, vec![b.id.clone()]);
+        assert_eq!(delete_tag(&file, "RUST")?.len(), 2);
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.links[0].tags, vec!["kubernetes"]);
+        assert_eq!(feed.links[1].tags, vec!["machine learning"]);
+        assert_eq!(feed.revision, 4);
+
+        assert!(merge_tags(&file, &["absent"], "x")?.is_empty());
+        assert_eq!(read_feed(&file)?.revision, 4, "no change, no write");
+        Ok(())
+    }
+
+    #[test]
+    fn add_with_options_can_keep_position() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let a = add(file.clone(), "A", "https://a/", None, None, None, None)?;
+        add(file.clone(), "B", "https://b/", None, None, None, None)?;
+
+        let options = AddOptions {
+            move_to_front: false,
+            ..Default::default()
+        };
+        let a2 = add_with_options(
+            file.clone(),
+            "A (fixed)",
+            "https://a/",
+            None,
+            None,
+            None,
+            None,
+            &options,
+        )?;
+        assert_eq!(a2.id, a.id);
+        assert_eq!(a2.created, a.created);
+
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.links[0].title, "B");
+        assert_eq!(feed.links[1].title, "A (fixed)");
+        Ok(())
+    }
+
+    #[test]
+    fn add_inserts_new_when_url_diff_and_id_absent() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let _a = add(file.clone(), "A", "https://a/", None, None, None, None)?;
+        let b = add(file.clone(), "B", "https://b/", None, None, None, None)?;
+
+        let feed = list(&file, None)?;
+        assert_eq!(feed.links.len(), 2);
+        assert_eq!(feed.links[0].id, b.id, "new item should be at front");
+        Ok(())
+    }
+
+    #[test]
+    fn add_releases_feed_lock() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        add(file.clone(), "A", "https://a/", None, None, None, None)?;
+        assert!(!lock_path(&file).exists(), "lock file should be removed");
+
+        // A held lock makes the next add wait; once released it proceeds.
+        let lock = FeedLock::acquire(&file)?;
+        let handle = {
+            let file = file.clone();
+            std::thread::spawn(move || add(file, "B", "https://b/", None, None, None, None))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(
+            read_feed(&file)?.links.len(),
+            1,
+            "add must not bypass the lock"
+        );
+        drop(lock);
+        handle.join().expect("add thread panicked")?;
+        assert_eq!(read_feed(&file)?.links.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn add_returns_error_on_corrupt_feed() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        // Write junk so read_feed(file) inside add() fails with decode error.
+        std::fs::write(&file, b"not a protobuf")?;
+
+        let err = add(file.clone(), "X", "https://x/", None, None, None, None).unwrap_err();
+
+        // The decode failure from read_feed is surfaced as-is.
+        assert!(matches!(err, Error::Decode { .. }), "got: {err}");
+        assert_eq!(err.path(), Some(file.as_path()));
+        Ok(())
+    }
 
     #[test]
-    fn add_creates_file_and_initializes_feed() -> Result<()> {
+    fn remove_by_id_persists_and_returns_link() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
 
-        // via=None & tags string -> defaults + parse_tags used internally
-        let created = add(
-            file.clone(),
-            "Tokio",
-            "https://tokio.rs/".into(),
-            None, // summary -> ""
-            vec!["rust", "async", "tokio"],
-            None,         // via -> ""
-            None::<Uuid>, // id -> generated
-        )?;
+        let a = add(file.clone(), "A", "https://a/", None, None, None, None)?;
+        let b = add(file.clone(), "B", "https://b/", None, None, None, None)?;
+
+        let removed = remove(&file, LinkSelector::id(a.id.clone()))?;
+        assert_eq!(removed, vec![a]);
 
-        // File exists and can be read; version initialized to 1
         let feed = read_feed(&file)?;
-        assert_eq!(feed.version, 1);
         assert_eq!(feed.links.len(), 1);
-        let l = &feed.links[0];
-        assert_eq!(l.id, created.id);
-        assert_eq!(l.title, "Tokio");
-        assert_eq!(l.url, "https://tokio.rs/");
-        assert_eq!(l.summary, None);
-        assert_eq!(l.via, None);
-        assert_eq!(l.tags, vec!["rust", "async", "tokio"]);
-
-        // ID is a valid UUID
-        let _ = Uuid::parse_str(&created.id).expect("id should be a valid UUID");
+        assert_eq!(feed.links[0].id, b.id);
+        assert_eq!(feed.revision, 3);
         Ok(())
     }
 
     #[test]
-    fn add_with_explicit_id_inserts_with_given_id() -> Result<()> {
+    fn remove_reports_link_not_found() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
-        let wanted = Uuid::new_v4();
-
-        let created = add(
-            file.clone(),
-            "A",
-            "https://a.example/".into(),
-            Some(Summary::new("hi")),
-            Some("x,y".into()),
-            Some(Via::new("via")),
-            Some(wanted),
-        )?;
+        add(file.clone(), "A", "https://a/", None, None, None, None)?;
 
-        assert_eq!(created.id, wanted.to_string());
+        let err = remove(&file, LinkSelector::url("https://missing/")).unwrap_err();
+        assert!(matches!(err, Error::LinkNotFound { .. }), "got: {err}");
+        assert_eq!(read_feed(&file)?.revision, 1, "nothing written");
 
-        // list(None, None) returns everything; first item is the one we just added
-        let feed = list(&file, None, None)?;
-        assert_eq!(feed.links.len(), 1);
-        assert_eq!(feed.links[0].id, wanted.to_string());
+        // A corrupt file is an I/O-level failure, not "not found".
+        std::fs::write(&file, b"junk")?;
+        let err = remove(&file, LinkSelector::url("https://a/")).unwrap_err();
+        assert!(matches!(err, Error::Decode { .. }), "got: {err}");
         Ok(())
     }
 
     #[test]
-    fn add_update_by_id_moves_to_front_and_updates_fields() -> Result<()> {
+    fn update_keeps_unpatched_fields() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
-        let tags = ["alpha"];
-        // Seed with two links
+
         let a = add(
             file.clone(),
-            "First",
-            "https://one/".into(),
-            None,
-            tags,
+            "Tokio",
+            "https://tokio.rs/",
+            Some(Summary::new("runtime")),
+            ["rust", "async"],
+            Some(Via::new("HN")),
             None,
-            None::<Uuid>,
         )?;
-        let _b = add(
+        add(
             file.clone(),
-            "Second",
-            "https://two/".into(),
+            "Other",
+            "https://other/",
+            None,
             None,
-            Some("beta".into()),
             None,
             None,
         )?;
 
-        // Update by id of 'a': title/url/tags/via/summary overwritten, item moves to front
-        let updated = add(
-            file.clone(),
-            "First (updated)",
-            "https://one-new/".into(),
-            Some(Summary::new("note")),
-            ["rust", "updated"],
-            Some(Via::new("HN")),
-            Some(Uuid::parse_str(&a.id)?),
-        )?;
-        assert_eq!(updated.id, a.id);
-        assert_eq!(updated.title, "First (updated)");
-        assert_eq!(updated.url, "https://one-new/");
-        assert_eq!(updated.summary, Some(Summary::new("note")));
+        let patch = LinkPatch::new()
+            .title("Tokio (fixed)")
+            .add_tag("tokio")
+            .preserve_position(true);
+        let updated = update(&file, &a.id, &patch)?;
+        assert_eq!(updated.title, "Tokio (fixed)");
+        assert_eq!(updated.tags, vec!["rust", "async", "tokio"]);
+        assert_eq!(updated.summary, Some(Summary::new("runtime")));
         assert_eq!(updated.via, Some(Via::new("HN")));
-        assert_eq!(updated.tags, vec!["rust", "updated"]);
+        assert_eq!(updated.datetime, a.datetime);
+        assert_eq!(updated.created, a.created);
 
-        let feed = list(&file, None, None)?;
-        assert_eq!(feed.links.len(), 2);
-        assert_eq!(feed.links[0].id, a.id, "updated item should be at index 0");
-        assert_eq!(feed.links[0].title, "First (updated)");
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.links[1].id, a.id, "position preserved");
+
+        let err = update(&file, "missing", &patch).unwrap_err();
+        assert!(matches!(err, Error::LinkNotFound { .. }));
+
+        let err = update(&file, &a.id, &LinkPatch::new().title("  ")).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)), "{err}");
+        assert_eq!(read_feed(&file)?, feed, "file untouched");
         Ok(())
     }
 
     #[test]
-    fn add_update_by_url_when_id_absent() -> Result<()> {
+    fn trashed_links_are_hidden_from_list_until_restored() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
 
-        let first = add(
-            file.clone(),
-            "Original",
-            "https://same.url/".into(),
-            None,
-            None,
-            None,
-            None,
-        )?;
+        let a = add(file.clone(), "A", "https://a/", None, ["x"], None, None)?;
+        add(file.clone(), "B", "https://b/", None, ["x"], None, None)?;
 
-        // Same URL, id=None => update-in-place (but moved to front) and id stays the same
-        let updated = add(
-            file.clone(),
-            "Original (updated)",
-            "https://same.url/".into(),
-            Some(Summary::new("s")),
-            ["t1", "t2"],
-            None,
-            None,
+        let trashed = trash(&file, LinkSelector::id(a.id.clone()))?;
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].deleted.is_some());
+
+        let visible = list(&file, None)?;
+        assert_eq!(visible.links.len(), 1);
+        assert_eq!(visible.links[0].title, "B");
+
+        let all = list_with(
+            &file,
+            &ListOptions {
+                include_trashed: true,
+                ..Default::default()
+            },
         )?;
-        assert_eq!(updated.id, first.id);
+        assert_eq!(all.links.len(), 2);
+
+        // Trashing twice is a "not found": nothing left to trash.
+        let err = trash(&file, LinkSelector::id(a.id.clone())).unwrap_err();
+        assert!(matches!(err, Error::LinkNotFound { .. }));
+
+        restore(&file, LinkSelector::id(a.id.clone()))?;
+        assert_eq!(list(&file, None)?.links.len(), 2);
+
+        let at = DateTime::parse("2025-03-14T09:00:00Z")?;
+        let options = TrashOptions {
+            clock: Some(Arc::new(FixedClock(at))),
+        };
+        let trashed = trash_with(&file, LinkSelector::id(a.id.clone()), &options)?;
+        assert_eq!(trashed[0].deleted, Some(at));
+        Ok(())
+    }
+
+    #[test]
+    fn merge_duplicates_persists_and_is_idempotent() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let dt = sample_link().datetime.unwrap();
+        let a = mk_link("a", "Tokio", "https://tokio.rs/", dt, &["rust"], "", "");
+        let b = mk_link("b", "Tokio", "https://tokio.rs", dt, &["async"], "", "");
+        write_feed(&file, mk_feed(vec![a, b]))?;
 
-        let feed = list(&file, None, None)?;
+        let merged = merge_duplicates(&file, &DedupOptions::default())?;
+        assert_eq!(merged.len(), 1);
+        let feed = read_feed(&file)?;
         assert_eq!(feed.links.len(), 1);
-        assert_eq!(feed.links[0].title, "Original (updated)");
-        assert_eq!(feed.links[0].tags, vec!["t1", "t2"]);
+        assert_eq!(feed.links[0].tags, vec!["rust", "async"]);
+        assert_eq!(feed.revision, 1);
+
+        assert!(merge_duplicates(&file, &DedupOptions::default())?.is_empty());
+        assert_eq!(
+            read_feed(&file)?.revision,
+            1,
+            "nothing to merge, nothing written"
+        );
         Ok(())
     }
 
     #[test]
-    fn add_inserts_new_when_url_diff_and_id_absent() -> Result<()> {
+    fn purge_trash_only_removes_old_tombstones() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
 
-        let _a = add(
-            file.clone(),
-            "A",
-            "https://a/".into(),
-            None,
-            None,
-            None,
-            None,
-        )?;
-        let b = add(
-            file.clone(),
-            "B",
-            "https://b/".into(),
-            None,
-            None,
-            None,
-            None,
-        )?;
+        let mut old = sample_link();
+        old.id = "old".into();
+        old.deleted = Some(DateTime {
+            year: 2020,
+            month: 1,
+            day: 1,
+            ..Default::default()
+        });
+        let mut recent = sample_link();
+        recent.id = "recent".into();
+        recent.deleted = Some(DateTime {
+            year: 2025,
+            month: 6,
+            day: 1,
+            ..Default::default()
+        });
+        let live = sample_link();
+        write_feed(&file, mk_feed(vec![old, recent, live]))?;
+
+        let cutoff = DateTime {
+            year: 2024,
+            month: 1,
+            day: 1,
+            ..Default::default()
+        };
+        let purged = purge_trash(&file, &cutoff)?;
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].id, "old");
 
-        let feed = list(&file, None, None)?;
-        assert_eq!(feed.links.len(), 2);
-        assert_eq!(feed.links[0].id, b.id, "new item should be at front");
+        let ids: Vec<String> = read_feed(&file)?.links.into_iter().map(|l| l.id).collect();
+        assert_eq!(ids, vec!["recent", "1234"]);
         Ok(())
     }
 
     #[test]
-    fn add_returns_error_on_corrupt_feed() -> Result<()> {
+    fn list_with_query_ands_with_legacy_filters() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("feed.pb");
 
-        // Write junk so read_feed(file) inside add() fails with decode error.
-        std::fs::write(&file, b"not a protobuf")?;
+        let day = |d| DateTime {
+            year: 2025,
+            month: 1,
+            day: d,
+            ..Default::default()
+        };
+        let l1 = mk_link("1", "One", "https://1/", day(2), &["rust", "async"], "", "");
+        let l2 = mk_link("2", "Two", "https://2/", day(3), &["rust"], "", "");
+        let l3 = mk_link("3", "Three", "https://3/", day(4), &["go"], "", "");
+        write_feed(&file, mk_feed(vec![l3, l2, l1]))?;
+
+        let since = time::macros::date!(2025 - 01 - 02);
+        let feed = list_with(&file, &Query::after(since).into())?;
+        let ids: Vec<_> = feed.links.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "2"]);
+
+        let feed = list_with(
+            &file,
+            &ListOptions {
+                tags: Some(vec!["rust".into()]),
+                query: Some(!Query::all_tags(["rust", "async"])),
+                ..Default::default()
+            },
+        )?;
+        let ids: Vec<_> = feed.links.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["2"]);
+        Ok(())
+    }
 
-        let err = add(
-            file.clone(),
-            "X",
-            "https://x/".into(),
-            None,
-            None,
-            None,
-            None,
-        )
-        .unwrap_err();
+    #[test]
+    fn list_page_walks_all_links_by_cursor() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let links = (1..=5)
+            .map(|d| {
+                let dt = DateTime {
+                    year: 2025,
+                    month: 1,
+                    day: d,
+                    ..Default::default()
+                };
+                let id = d.to_string();
+                mk_link(&id, &format!("T{d}"), "https://x/", dt, &[], "", "")
+            })
+            .collect();
+        write_feed(&file, mk_feed(links))?;
+
+        let mut options = ListOptions {
+            sort: SortKey::DatetimeDesc,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = list_page(&file, &options)?;
+            assert_eq!(page.total, 5);
+            seen.extend(page.links.into_iter().map(|l| l.id));
+            match page.next_cursor {
+                Some(c) => options.cursor = Some(c),
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec!["5", "4", "3", "2", "1"]);
+
+        options.cursor = Some("4".into());
+        options.offset = 1;
+        let page = list_page(&file, &options)?;
+        let ids: Vec<_> = page.links.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "1"]);
+        assert_eq!(page.next_cursor, None);
+
+        options.cursor = Some("gone".into());
+        let err = list_page(&file, &options).unwrap_err();
+        assert!(matches!(err, Error::LinkNotFound { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn list_page_cursor_survives_removing_its_link() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let links = (1..=5)
+            .map(|d| {
+                let dt = DateTime {
+                    year: 2025,
+                    month: 1,
+                    day: d,
+                    ..Default::default()
+                };
+                mk_link(
+                    &d.to_string(),
+                    &format!("T{d}"),
+                    "https://x/",
+                    dt,
+                    &[],
+                    "",
+                    "",
+                )
+            })
+            .collect();
+        write_feed(&file, mk_feed(links))?;
+
+        for sort in [SortKey::DatetimeDesc, SortKey::Title, SortKey::Stored] {
+            let mut options = ListOptions {
+                sort,
+                limit: Some(2),
+                ..Default::default()
+            };
+            let first = list_page(&file, &options)?;
+            let last_id = first.links[1].id.clone();
+            options.cursor = first.next_cursor;
+
+            // The last link of the page goes away before the next fetch.
+            let mut feed = read_feed(&file)?;
+            feed.links.retain(|l| l.id != last_id);
+            let backup = std::fs::read(&file)?;
+            write_feed(&file, feed)?;
+
+            let next = list_page(&file, &options)?;
+            let ids: Vec<_> = next.links.iter().map(|l| l.id.as_str()).collect();
+            let expected = match sort {
+                SortKey::DatetimeDesc => vec!["3", "2"],
+                _ => vec!["3", "4"],
+            };
+            assert_eq!(ids, expected, "{sort:?}");
+            std::fs::write(&file, backup)?;
+
+            options.sort = SortKey::Url;
+            let err = list_page(&file, &options).unwrap_err();
+            assert!(matches!(err, Error::Validation(_)), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn list_with_sorts_by_title_url_and_domain() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
 
-        // Just assert it is an error; message content is from read_feed context.
-        assert!(!err.to_string().is_empty());
+        let dt = DateTime::default();
+        let a = mk_link("a", "beta", "https://B.example/z", dt, &[], "", "");
+        let b = mk_link(
+            "b",
+            "Alpha",
+            "https://user@a.example:8080/y",
+            dt,
+            &[],
+            "",
+            "",
+        );
+        let c = mk_link("c", "gamma", "http://c.example/x", dt, &[], "", "");
+        write_feed(&file, mk_feed(vec![a, b, c]))?;
+
+        let ids = |sort| -> Result<Vec<String>> {
+            let options = ListOptions {
+                sort,
+                ..Default::default()
+            };
+            Ok(list_with(&file, &options)?
+                .links
+                .into_iter()
+                .map(|l| l.id)
+                .collect())
+        };
+        assert_eq!(ids(SortKey::Stored)?, vec!["a", "b", "c"]);
+        assert_eq!(ids(SortKey::Title)?, vec!["b", "a", "c"]);
+        assert_eq!(ids(SortKey::Url)?, vec!["c", "a", "b"]);
+        assert_eq!(ids(SortKey::Domain)?, vec!["b", "a", "c"]);
         Ok(())
     }
 
+    #[test]
+    fn url_host_strips_userinfo_and_port() {
+        assert_eq!(url_host("https://example.com/a?b#c"), "example.com");
+        assert_eq!(url_host("https://u:p@example.com:8443"), "example.com");
+        assert_eq!(url_host("http://[::1]:8080/"), "[::1]");
+        assert_eq!(url_host("mailto:someone@example.com"), "");
+    }
+
     #[test]
     fn list_without_filters_returns_all() -> Result<()> {
         let dir = tempdir()?;
@@ -790,6 +1969,7 @@ mod tests {
             minutes: 0,
             seconds: 0,
             nanos: 0,
+            utc_offset: None,
         };
 
         let dt2 = DateTime {
@@ -800,6 +1980,7 @@ mod tests {
             minutes: 30,
             seconds: 15,
             nanos: 0,
+            utc_offset: None,
         };
 
         // Build a feed directly so we control dates/tags precisely
@@ -807,7 +1988,7 @@ mod tests {
         let l2 = mk_link("2", "Two", "https://2/", dt2, &["tokio"], "", "");
         write_feed(&file, mk_feed(vec![l2.clone(), l1.clone()]))?;
 
-        let feed = list(&file, None, None)?;
+        let feed = list(&file, None)?;
         assert_eq!(feed.links.len(), 2);
         // Order is preserved from the stored feed for list()
         assert_eq!(feed.links[0].id, l2.id);
@@ -828,6 +2009,7 @@ mod tests {
             minutes: 0,
             seconds: 0,
             nanos: 0,
+            utc_offset: None,
         };
 
         let dt2 = DateTime {
@@ -838,6 +2020,7 @@ mod tests {
             minutes: 30,
             seconds: 15,
             nanos: 0,
+            utc_offset: None,
         };
 
         let l1 = mk_link("1", "One", "https://1/", dt1, &["rust", "async"], "", "");
@@ -853,19 +2036,144 @@ mod tests {
         write_feed(&file, mk_feed(vec![l1.clone(), l2.clone()]))?;
 
         // ANY-of semantics, case-insensitive
-        let feed_tokio = list(&file, Some(vec!["tokio".into()]), None)?;
+        let feed_tokio = list(
+            &file,
+            ListOptions {
+                tags: Some(vec!["tokio".into()]),
+                ..Default::default()
+            },
+        )?;
         assert_eq!(feed_tokio.links.len(), 1);
         assert_eq!(feed_tokio.links[0].id, l2.id);
 
-        let feed_async = list(&file, Some(vec!["ASYNC".into()]), None)?;
+        let feed_async = list(
+            &file,
+            ListOptions {
+                tags: Some(vec!["ASYNC".into()]),
+                ..Default::default()
+            },
+        )?;
         assert_eq!(feed_async.links.len(), 1);
         assert_eq!(feed_async.links[0].id, l1.id);
 
         // Multiple needles -> still "any"
-        let feed_multi = list(&file, Some(vec!["zzz".into(), "rust".into()]), None)?;
+        let feed_multi = list(
+            &file,
+            ListOptions {
+                tags: Some(vec!["zzz".into(), "rust".into()]),
+                ..Default::default()
+            },
+        )?;
         assert_eq!(feed_multi.links.len(), 1);
         assert_eq!(feed_multi.links[0].id, l1.id);
 
+        // A Query works directly.
+        let all_of = list(&file, Query::all_tags(["rust", "async"]))?;
+        assert_eq!(all_of.links.len(), 1);
+        assert_eq!(all_of.links[0].id, l1.id);
+        assert!(
+            list(&file, Query::no_tags(["rust", "tokio"]))?
+                .links
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn list_tag_filters_match_hierarchically() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let dt = DateTime::default();
+        let rust = mk_link("1", "One", "https://1/", dt, &["lang/rust"], "", "");
+        let go = mk_link("2", "Two", "https://2/", dt, &["Lang/Go"], "", "");
+        let other = mk_link("3", "Three", "https://3/", dt, &["language"], "", "");
+        write_feed(&file, mk_feed(vec![rust, go, other]))?;
+
+        let ids = |feed: Feed| feed.links.into_iter().map(|l| l.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(list(
+                &file,
+                ListOptions {
+                    tags: Some(vec!["lang".into()]),
+                    ..Default::default()
+                }
+            )?),
+            vec!["1", "2"]
+        );
+        assert_eq!(
+            ids(list(
+                &file,
+                ListOptions {
+                    tags: Some(vec!["LANG/go".into()]),
+                    ..Default::default()
+                }
+            )?),
+            vec!["2"]
+        );
+        let q = ListOptions::from(Query::all_tags(["lang"]).and(!Query::any_tag(["lang/go"])));
+        assert_eq!(ids(list_with(&file, &q)?), vec!["1"]);
+        Ok(())
+    }
+
+    #[test]
+    fn tag_aliases_apply_to_filters_and_new_tags() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let a = add(
+            file.clone(),
+            "A",
+            "https://a/",
+            None,
+            ["kubernetes"],
+            None,
+            None,
+        )?;
+        define_tag_alias(&file, "K8s", "kubernetes")?;
+        assert_eq!(read_feed(&file)?.tag_aliases["k8s"], "kubernetes");
+
+        let b = add(
+            file.clone(),
+            "B",
+            "https://b/",
+            None,
+            ["k8s/helm"],
+            None,
+            None,
+        )?;
+        assert_eq!(b.tags, vec!["kubernetes/helm"]);
+        let ids = |feed: Feed| feed.links.into_iter().map(|l| l.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(list(
+                &file,
+                ListOptions {
+                    tags: Some(vec!["k8s".into()]),
+                    ..Default::default()
+                }
+            )?),
+            vec![b.id.clone(), a.id.clone()]
+        );
+
+        assert_eq!(
+            remove_tag_alias(&file, "k8s")?.as_deref(),
+            Some("kubernetes")
+        );
+        assert_eq!(remove_tag_alias(&file, "k8s")?, None);
+        assert!(
+            list(
+                &file,
+                ListOptions {
+                    tags: Some(vec!["k8s".into()]),
+                    ..Default::default()
+                }
+            )?
+            .links
+            .is_empty()
+        );
+        assert!(matches!(
+            define_tag_alias(&file, "kube", "kube/x"),
+            Err(Error::Validation(_))
+        ));
         Ok(())
     }
 
@@ -882,6 +2190,7 @@ mod tests {
             minutes: 0,
             seconds: 0,
             nanos: 0,
+            utc_offset: None,
         };
 
         let dt2 = DateTime {
@@ -892,17 +2201,30 @@ mod tests {
             minutes: 59,
             seconds: 59,
             nanos: 0,
+            utc_offset: None,
         };
 
         let l1 = mk_link("1", "Jan02", "https://1/", dt1, &[], "", "");
         let l2 = mk_link("2", "Jan03", "https://2/", dt2, &[], "", "");
         write_feed(&file, mk_feed(vec![l1.clone(), l2.clone()]))?;
 
-        let filtered = list(&file, None, Some(dt2))?;
+        let filtered = list(
+            &file,
+            ListOptions {
+                datetime: Some(dt2),
+                ..Default::default()
+            },
+        )?;
         assert_eq!(filtered.links.len(), 1);
         assert_eq!(filtered.links[0].id, l2.id);
 
-        let filtered2 = list(&file, None, Some(dt1))?;
+        let filtered2 = list(
+            &file,
+            ListOptions {
+                datetime: Some(dt1),
+                ..Default::default()
+            },
+        )?;
         assert_eq!(filtered2.links.len(), 1);
         assert_eq!(filtered2.links[0].id, l1.id);
 
@@ -922,6 +2244,45 @@ mod tests {
         assert!(item.pub_date.is_some());
     }
 
+    #[test]
+    fn rss_pub_date_honors_utc_offset() {
+        let mut link = sample_link();
+        let local = link.datetime.unwrap();
+        assert_eq!(
+            local.to_rfc2822().as_deref(),
+            Some("Wed, 1 Oct 2025 14:30:45 +0000"),
+            "no offset: written as UTC"
+        );
+
+        link.created = Some(local.with_utc_offset(2 * 3600));
+        let item = link_to_rss_item(&link);
+        assert_eq!(
+            item.pub_date.as_deref(),
+            Some("Wed, 1 Oct 2025 14:30:45 +0200")
+        );
+    }
+
+    #[test]
+    fn rfc2822_rejects_invalid_fields_and_keeps_leap_seconds() -> Result<()> {
+        let valid = DateTime::parse("2025-02-28T12:00:00Z")?;
+        for (month, day, seconds) in [(13, 1, 0), (2, 30, 0), (2, 28, 61)] {
+            let dt = DateTime {
+                month,
+                day,
+                seconds,
+                ..valid
+            };
+            assert_eq!(dt.to_rfc2822(), None, "{month}-{day} :{seconds}");
+        }
+
+        let leap = DateTime::parse("2016-12-31T23:59:60Z")?;
+        assert_eq!(
+            leap.to_rfc2822().as_deref(),
+            Some("Sat, 31 Dec 2016 23:59:60 +0000")
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_feed_to_rss_xml_basic() {
         let feed = sample_feed();
@@ -941,6 +2302,64 @@ mod tests {
         assert!(rss_xml.contains("urn:uuid:1234"));
     }
 
+    #[test]
+    fn test_feed_to_rss_xml_with_leap_second() -> Result<()> {
+        let leap = chrono::DateTime::parse_from_rfc3339("2016-12-31T23:59:60Z")?;
+        let mut feed = sample_feed();
+        feed.links[0].created = Some(DateTime::from(leap));
+        let issues = crate::check::check(&feed);
+        assert!(
+            !issues
+                .iter()
+                .any(|i| matches!(i, crate::check::Issue::InvalidDateTime { .. })),
+            "check accepts a leap second"
+        );
+
+        let rss_xml = feed_to_rss_xml(&feed, "Default Site", "https://example.com")?;
+        assert!(
+            rss_xml.contains("<pubDate>Sat, 31 Dec 2016 23:59:60 +0000</pubDate>"),
+            "{rss_xml}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parsed_leap_second_roundtrips_through_rss() -> Result<()> {
+        let leap = DateTime::parse("2016-12-31T23:59:60Z")?;
+        let mut feed = sample_feed();
+        feed.links[0].created = Some(leap);
+
+        let rss_xml = feed_to_rss_xml(&feed, "Default Site", "https://example.com")?;
+        let channel = rss::Channel::read_from(rss_xml.as_bytes())?;
+        let pub_date = channel.items[0].pub_date().expect("pubDate");
+        assert_eq!(pub_date, "Sat, 31 Dec 2016 23:59:60 +0000");
+        assert_eq!(DateTime::parse(pub_date)?, leap);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_to_rss_xml_skips_trashed_by_default() {
+        let mut feed = sample_feed();
+        feed.links[0].deleted = Some(DateTime {
+            year: 2025,
+            month: 10,
+            day: 2,
+            ..Default::default()
+        });
+
+        let rss_xml = feed_to_rss_xml(&feed, "Default Site", "https://example.com")
+            .expect("Failed to generate RSS XML");
+        assert!(!rss_xml.contains("Example Post"));
+
+        let options = RssOptions {
+            include_trashed: true,
+        };
+        let rss_xml =
+            feed_to_rss_xml_with_options(&feed, "Default Site", "https://example.com", &options)
+                .expect("Failed to generate RSS XML");
+        assert!(rss_xml.contains("Example Post"));
+    }
+
     #[test]
     fn test_feed_to_rss_xml_empty_feed_title() {
         let mut feed = sample_feed();
@@ -963,6 +2382,7 @@ mod tests {
             summary: None,
             tags: vec![],
             datetime: None,
+            ..Default::default()
         };
 
         let item = link_to_rss_item(&link);
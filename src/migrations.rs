@@ -0,0 +1,306 @@
+//! Upgrades of older feed formats to [`CURRENT_VERSION`].
+//!
+//! [`read_feed`](crate::fs::read_feed) runs [`migrate`] on every feed it
+//! decodes, so the rest of the crate only sees current feeds. Each
+//! [`Migration`] in [`MIGRATIONS`] upgrades one version to the next; they are
+//! applied in order. Feeds from a newer version are refused with
+//! [`Error::UnsupportedVersion`] rather than silently dropping what this
+//! version does not understand.
+//!
+//! The upgraded version reaches the file with the next write: a
+//! [`FeedStore`](crate::FeedStore) that migrated the feed it opened is dirty,
+//! so its next [`commit`](crate::FeedStore::commit) persists the upgrade.
+//! [`migrate_file`] does it right away.
+//!
+//! ## Adding a version
+//! Bump [`CURRENT_VERSION`] and append a [`Migration`] from the previous
+//! version, with a golden fixture of the old format under `tests/fixtures`.
+
+use crate::error::{Error, Result};
+use crate::fs::{FeedLock, decode_feed, write_feed};
+use crate::linkleaf_proto::Feed;
+use std::path::Path;
+
+/// The feed format version written by this crate.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// One upgrade step from version `from` to `from + 1`.
+#[derive(Debug)]
+pub struct Migration {
+    /// The version this step upgrades from.
+    pub from: u32,
+    /// What the step changes.
+    pub description: &'static str,
+    apply: fn(&mut Feed),
+}
+
+impl Migration {
+    /// The version this step upgrades to.
+    pub fn to(&self) -> u32 {
+        self.from + 1
+    }
+}
+
+/// Every upgrade step, oldest first.
+pub static MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "add link created/updated timestamps, backfilled from datetime",
+    apply: backfill_timestamps,
+}];
+
+/// Upgrade `feed` in memory to [`CURRENT_VERSION`].
+///
+/// ## Behavior
+/// - Version 0 (feeds written before the version was set) is read as
+///   version 1.
+/// - Applies every [`Migration`] from the feed's version on, in order, and
+///   sets `Feed.version` to [`CURRENT_VERSION`].
+/// - Current feeds are left untouched.
+///
+/// ## Returns
+/// The applied steps (empty if the feed was current).
+///
+/// ## Errors
+/// - [`Error::UnsupportedVersion`] if the feed is newer than
+///   [`CURRENT_VERSION`].
+/// - [`Error::Validation`] if [`MIGRATIONS`] has no step for one of the
+///   versions on the way (a bug in this crate).
+///
+/// The feed is left untouched on error.
+pub fn migrate(feed: &mut Feed) -> Result<Vec<&'static Migration>> {
+    if feed.version > CURRENT_VERSION {
+        return Err(Error::UnsupportedVersion {
+            version: feed.version,
+            supported: CURRENT_VERSION,
+        });
+    }
+    let steps = plan(MIGRATIONS, feed.version.max(1))?;
+    if feed.version == 0 {
+        feed.version = 1;
+    }
+
+    for step in &steps {
+        (step.apply)(feed);
+        feed.version = step.to();
+        #[cfg(feature = "logs")]
+        tracing::info!(from = step.from, to = step.to(), "migrated feed");
+    }
+    Ok(steps)
+}
+
+/// The steps of `migrations` leading from version `from` to
+/// [`CURRENT_VERSION`], in order.
+fn plan(migrations: &[Migration], from: u32) -> Result<Vec<&Migration>> {
+    let mut steps = Vec::new();
+    let mut version = from;
+    while version < CURRENT_VERSION {
+        let step = migrations
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| {
+                Error::Validation(format!(
+                    "no migration from feed format version {version} to {}",
+                    version + 1
+                ))
+            })?;
+        steps.push(step);
+        version = step.to();
+    }
+    Ok(steps)
+}
+
+/// Upgrade the feed file at `path` to [`CURRENT_VERSION`] and write it back.
+///
+/// ## Behavior
+/// Holds the feed's [`FeedLock`] while reading, migrating and writing. The
+/// file is only rewritten (with its `revision` bumped) if a step was applied.
+///
+/// ## Returns
+/// The applied steps.
+///
+/// ## Errors
+/// [`Error::UnsupportedVersion`] for feeds newer than [`CURRENT_VERSION`],
+/// plus [`Error::Lock`], decode and I/O errors.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::migrations::migrate_file;
+///
+/// for step in migrate_file("mylinks.pb")? {
+///     println!("v{} -> v{}: {}", step.from, step.to(), step.description);
+/// }
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn migrate_file<P: AsRef<Path>>(path: P) -> Result<Vec<&'static Migration>> {
+    let path = path.as_ref();
+    let _lock = FeedLock::acquire(path)?;
+    let mut feed = decode_feed(path)?;
+    let applied = migrate(&mut feed)?;
+    if !applied.is_empty() {
+        feed.revision += 1;
+        write_feed(path, feed)?;
+    }
+    Ok(applied)
+}
+
+/// v1 → v2: links written before `created`/`updated` existed get them from
+/// `datetime`.
+fn backfill_timestamps(feed: &mut Feed) {
+    for l in &mut feed.links {
+        if l.created.is_none() {
+            l.created = l.datetime;
+        }
+        if l.updated.is_none() {
+            l.updated = l.created;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CURRENT_VERSION, MIGRATIONS, Migration, migrate, migrate_file, plan};
+    use crate::error::Error;
+    use crate::fs::{decode_feed, read_feed};
+    use crate::linkleaf_proto::{DateTime, Feed};
+    use crate::{FeedStore, LinkSelector};
+    use anyhow::Result;
+    use prost::Message;
+    use tempfile::tempdir;
+
+    const FEED_V0: &[u8] = include_bytes!("../tests/fixtures/feed_v0.pb");
+    const FEED_V1: &[u8] = include_bytes!("../tests/fixtures/feed_v1.pb");
+
+    #[test]
+    fn steps_chain_up_to_the_current_version() {
+        let mut version = 1;
+        for step in MIGRATIONS {
+            assert_eq!(step.from, version);
+            version = step.to();
+        }
+        assert_eq!(version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn golden_v1_and_v0_feeds_upgrade_on_read() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        std::fs::write(&file, FEED_V1)?;
+
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.version, CURRENT_VERSION);
+        assert_eq!(feed.revision, 7);
+        let tokio = &feed.links[0];
+        assert_eq!(tokio.title, "Tokio - Asynchronous Rust");
+        assert_eq!(tokio.tags, vec!["rust", "async"]);
+        assert_eq!(tokio.created, tokio.datetime);
+        assert_eq!(tokio.updated, tokio.datetime);
+        assert_eq!(
+            feed.links[1].created,
+            Some(DateTime {
+                year: 2024,
+                month: 12,
+                day: 1,
+                hours: 18,
+                ..Default::default()
+            })
+        );
+        // Reading does not write.
+        assert_eq!(std::fs::read(&file)?, FEED_V1);
+
+        let mut legacy = Feed::decode(FEED_V0)?;
+        assert_eq!(legacy.version, 0);
+        assert_eq!(migrate(&mut legacy)?.len(), 1);
+        assert_eq!(legacy.version, CURRENT_VERSION);
+        assert!(legacy.links[0].created.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_file_writes_back_once() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        std::fs::write(&file, FEED_V1)?;
+
+        assert_eq!(migrate_file(&file)?.len(), 1);
+        let raw = decode_feed(&file)?;
+        assert_eq!(raw.version, CURRENT_VERSION);
+        assert_eq!(raw.revision, 8);
+        assert!(raw.links.iter().all(|l| l.created.is_some()));
+
+        assert!(migrate_file(&file)?.is_empty(), "already current");
+        assert_eq!(decode_feed(&file)?.revision, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn a_gap_in_the_steps_is_an_error() {
+        let gapped = [Migration {
+            from: 0,
+            description: "not the step from 1",
+            apply: |_| {},
+        }];
+        assert!(matches!(plan(&gapped, 1), Err(Error::Validation(_))));
+        assert_eq!(plan(MIGRATIONS, 1).unwrap().len(), MIGRATIONS.len());
+        assert!(plan(&[], CURRENT_VERSION).unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_persists_the_upgrade_on_commit() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        std::fs::write(&file, FEED_V1)?;
+
+        let mut store = FeedStore::open(&file)?;
+        assert!(store.is_dirty(), "migrated on open");
+        store.commit()?;
+        let raw = decode_feed(&file)?;
+        assert_eq!(raw.version, CURRENT_VERSION);
+        assert_eq!(raw.revision, 8);
+        drop(store);
+
+        assert!(!FeedStore::open(&file)?.is_dirty(), "already current");
+        Ok(())
+    }
+
+    #[test]
+    fn one_shot_functions_only_write_the_upgrade_with_a_change() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        std::fs::write(&file, FEED_V1)?;
+
+        let missing = || LinkSelector::url("https://missing/");
+        assert!(crate::remove(&file, missing()).is_err());
+        assert!(crate::trash(&file, missing()).is_err());
+        assert!(crate::restore(&file, missing()).is_err());
+        assert!(crate::rename_tag(&file, "missing", "other")?.is_empty());
+        assert_eq!(std::fs::read(&file)?, FEED_V1, "file untouched");
+
+        let tokio = read_feed(&file)?.links[0].url.clone();
+        crate::trash(&file, LinkSelector::url(tokio))?;
+        let raw = decode_feed(&file)?;
+        assert_eq!(raw.version, CURRENT_VERSION);
+        assert_eq!(raw.revision, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn future_versions_are_refused() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let future = Feed {
+            version: CURRENT_VERSION + 1,
+            ..Default::default()
+        };
+        std::fs::write(&file, future.encode_to_vec())?;
+
+        let err = read_feed(&file).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnsupportedVersion { version, supported: CURRENT_VERSION }
+                if version == CURRENT_VERSION + 1
+        ));
+        assert!(err.to_string().contains("newer"), "{err}");
+        assert!(migrate_file(&file).is_err());
+        Ok(())
+    }
+}
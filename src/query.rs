@@ -0,0 +1,440 @@
+//! Composable link filters for [`list_with`](crate::list_with) and
+//! [`FeedStore::query`](crate::FeedStore::query).
+//!
+//! A [`Query`] is a small expression tree: leaf predicates on tags, dates,
+//! text fields and `via`, combined with [`Query::and`], [`Query::or`] and `!`.
+//!
+//! ```
+//! use linkleaf_core::query::Query;
+//! use linkleaf_core::validation::parse_date;
+//!
+//! let q = Query::all_tags(["rust", "async"])
+//!     .and(Query::between(parse_date("2025-01-01")?, parse_date("2025-03-31")?))
+//!     .and(!Query::title_contains("draft"));
+//! # Ok::<(), String>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::fuzzy::fuzzy_match;
+use crate::linkleaf_proto::{DateTime, Link};
+use crate::store::instant_key;
+use crate::tags::{resolve_tag, tag_matches};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use time::Date;
+
+/// A filter over [`Link`]s.
+#[derive(Debug, Clone, Default)]
+pub enum Query {
+    /// Matches every link.
+    #[default]
+    All,
+    /// The link has at least one of these tags.
+    AnyTag(Vec<String>),
+    /// The link has every one of these tags.
+    AllTags(Vec<String>),
+    /// The link has none of these tags.
+    NoTags(Vec<String>),
+    /// The link's creation time falls in this range.
+    Date(DateRange),
+    /// A text field of the link matches.
+    Text(TextField, TextMatch),
+    /// The link's `via` URL matches. Links without `via` never match.
+    Via(TextMatch),
+    /// Title or summary matches these words, with typos tolerated (see
+    /// [`crate::fuzzy`]).
+    Fuzzy(String),
+    /// Every sub-query matches (an empty list matches everything).
+    And(Vec<Query>),
+    /// At least one sub-query matches (an empty list matches nothing).
+    Or(Vec<Query>),
+    /// The sub-query does not match.
+    Not(Box<Query>),
+}
+
+/// Which text field a [`Query::Text`] looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    Title,
+    Summary,
+    Url,
+    /// Title, summary or URL.
+    Any,
+}
+
+/// How a text field is matched.
+#[derive(Debug, Clone)]
+pub enum TextMatch {
+    /// Case-insensitive substring.
+    Contains(String),
+    /// Regular expression (see the [`regex`] crate for syntax).
+    Regex(Regex),
+}
+
+/// A point in time a [`DateRange`] is bounded by.
+///
+/// A [`DateBound::Date`] covers the whole calendar day: only the date part of
+/// a link's timestamp is compared against it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateBound {
+    Date(Date),
+    DateTime(DateTime),
+}
+
+/// An optional lower and upper bound on a link's creation time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DateRange {
+    /// Lower bound; see [`DateRange::inclusive`].
+    pub start: Option<DateBound>,
+    /// Upper bound; see [`DateRange::inclusive`].
+    pub end: Option<DateBound>,
+    /// Whether the bounds themselves match. `Query::between` is inclusive,
+    /// `Query::before` and `Query::after` are exclusive.
+    pub inclusive: bool,
+}
+
+impl From<Date> for DateBound {
+    fn from(d: Date) -> Self {
+        DateBound::Date(d)
+    }
+}
+
+impl From<DateTime> for DateBound {
+    fn from(dt: DateTime) -> Self {
+        DateBound::DateTime(dt)
+    }
+}
+
+impl Query {
+    /// Links with at least one of `tags`, matched folded and hierarchically (see
+    /// [`tag_matches`]).
+    pub fn any_tag<S: Into<String>, I: IntoIterator<Item = S>>(tags: I) -> Self {
+        Query::AnyTag(collect_tags(tags))
+    }
+
+    /// Links with every one of `tags`, matched folded and hierarchically (see
+    /// [`tag_matches`]).
+    pub fn all_tags<S: Into<String>, I: IntoIterator<Item = S>>(tags: I) -> Self {
+        Query::AllTags(collect_tags(tags))
+    }
+
+    /// Links with none of `tags`, matched folded and hierarchically (see
+    /// [`tag_matches`]).
+    pub fn no_tags<S: Into<String>, I: IntoIterator<Item = S>>(tags: I) -> Self {
+        Query::NoTags(collect_tags(tags))
+    }
+
+    /// Links created strictly before `bound` (before that day, for a date).
+    pub fn before<B: Into<DateBound>>(bound: B) -> Self {
+        Query::Date(DateRange {
+            start: None,
+            end: Some(bound.into()),
+            inclusive: false,
+        })
+    }
+
+    /// Links created strictly after `bound` (after that day, for a date).
+    pub fn after<B: Into<DateBound>>(bound: B) -> Self {
+        Query::Date(DateRange {
+            start: Some(bound.into()),
+            end: None,
+            inclusive: false,
+        })
+    }
+
+    /// Links created between `start` and `end`, both inclusive.
+    pub fn between<A: Into<DateBound>, B: Into<DateBound>>(start: A, end: B) -> Self {
+        Query::Date(DateRange {
+            start: Some(start.into()),
+            end: Some(end.into()),
+            inclusive: true,
+        })
+    }
+
+    /// Links created on the calendar day `date`.
+    pub fn on(date: Date) -> Self {
+        Query::between(date, date)
+    }
+
+    /// Title contains `needle` (case-insensitive).
+    pub fn title_contains<S: Into<String>>(needle: S) -> Self {
+        Query::Text(TextField::Title, TextMatch::Contains(needle.into()))
+    }
+
+    /// Summary contains `needle` (case-insensitive).
+    pub fn summary_contains<S: Into<String>>(needle: S) -> Self {
+        Query::Text(TextField::Summary, TextMatch::Contains(needle.into()))
+    }
+
+    /// URL contains `needle` (case-insensitive).
+    pub fn url_contains<S: Into<String>>(needle: S) -> Self {
+        Query::Text(TextField::Url, TextMatch::Contains(needle.into()))
+    }
+
+    /// Title, summary or URL contains `needle` (case-insensitive).
+    pub fn text_contains<S: Into<String>>(needle: S) -> Self {
+        Query::Text(TextField::Any, TextMatch::Contains(needle.into()))
+    }
+
+    /// `field` matches the regular expression `pattern`.
+    ///
+    /// ## Errors
+    /// [`Error::Validation`] if `pattern` is not a valid regex.
+    pub fn matches_regex(field: TextField, pattern: &str) -> Result<Self> {
+        Ok(Query::Text(field, TextMatch::Regex(compile(pattern)?)))
+    }
+
+    /// `via` URL contains `needle` (case-insensitive).
+    pub fn via_contains<S: Into<String>>(needle: S) -> Self {
+        Query::Via(TextMatch::Contains(needle.into()))
+    }
+
+    /// `via` URL matches the regular expression `pattern`.
+    ///
+    /// ## Errors
+    /// [`Error::Validation`] if `pattern` is not a valid regex.
+    pub fn via_matches(pattern: &str) -> Result<Self> {
+        Ok(Query::Via(TextMatch::Regex(compile(pattern)?)))
+    }
+
+    /// Title or summary fuzzily matches every word of `text`.
+    pub fn fuzzy<S: Into<String>>(text: S) -> Self {
+        Query::Fuzzy(text.into())
+    }
+
+    /// Both `self` and `other` match.
+    pub fn and(self, other: Query) -> Self {
+        match self {
+            Query::And(mut qs) => {
+                qs.push(other);
+                Query::And(qs)
+            }
+            q => Query::And(vec![q, other]),
+        }
+    }
+
+    /// `self` or `other` matches.
+    pub fn or(self, other: Query) -> Self {
+        match self {
+            Query::Or(mut qs) => {
+                qs.push(other);
+                Query::Or(qs)
+            }
+            q => Query::Or(vec![q, other]),
+        }
+    }
+
+    /// `true` if `link` satisfies this query.
+    pub fn matches(&self, link: &Link) -> bool {
+        self.matches_with_aliases(link, &BTreeMap::new())
+    }
+
+    /// Like [`Query::matches`], resolving tag aliases (a feed's
+    /// `tag_aliases`) on both sides of tag predicates, so `k8s` matches links
+    /// tagged `kubernetes` and vice versa.
+    pub fn matches_with_aliases(&self, link: &Link, aliases: &BTreeMap<String, String>) -> bool {
+        let has_tag = |needle: &String| has_tag(link, needle, aliases);
+        match self {
+            Query::All => true,
+            Query::AnyTag(needles) => needles.iter().any(has_tag),
+            Query::AllTags(needles) => needles.iter().all(has_tag),
+            Query::NoTags(needles) => !needles.iter().any(has_tag),
+            Query::Date(range) => link_time(link).is_some_and(|t| range.contains(t)),
+            Query::Text(field, m) => {
+                let summary = link.summary.as_ref().map(|s| s.content.as_str());
+                match field {
+                    TextField::Title => m.is_match(&link.title),
+                    TextField::Summary => summary.is_some_and(|s| m.is_match(s)),
+                    TextField::Url => m.is_match(&link.url),
+                    TextField::Any => {
+                        m.is_match(&link.title)
+                            || summary.is_some_and(|s| m.is_match(s))
+                            || m.is_match(&link.url)
+                    }
+                }
+            }
+            Query::Via(m) => link.via.as_ref().is_some_and(|v| m.is_match(&v.url)),
+            Query::Fuzzy(text) => fuzzy_match(link, text).is_some(),
+            Query::And(qs) => qs.iter().all(|q| q.matches_with_aliases(link, aliases)),
+            Query::Or(qs) => qs.iter().any(|q| q.matches_with_aliases(link, aliases)),
+            Query::Not(q) => !q.matches_with_aliases(link, aliases),
+        }
+    }
+}
+
+impl std::ops::Not for Query {
+    type Output = Query;
+
+    fn not(self) -> Query {
+        Query::Not(Box::new(self))
+    }
+}
+
+impl TextMatch {
+    /// `true` if `haystack` matches.
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            TextMatch::Contains(needle) => haystack.to_lowercase().contains(&needle.to_lowercase()),
+            TextMatch::Regex(re) => re.is_match(haystack),
+        }
+    }
+}
+
+impl DateRange {
+    /// `true` if `t` lies within the range.
+    pub fn contains(&self, t: &DateTime) -> bool {
+        let above = self.start.as_ref().is_none_or(|b| match cmp_bound(t, b) {
+            Ordering::Greater => true,
+            Ordering::Equal => self.inclusive,
+            Ordering::Less => false,
+        });
+        let below = self.end.as_ref().is_none_or(|b| match cmp_bound(t, b) {
+            Ordering::Less => true,
+            Ordering::Equal => self.inclusive,
+            Ordering::Greater => false,
+        });
+        above && below
+    }
+}
+
+/// The timestamp date filters apply to: `created`, falling back to `datetime`.
+fn link_time(link: &Link) -> Option<&DateTime> {
+    link.created.as_ref().or(link.datetime.as_ref())
+}
+
+fn cmp_bound(t: &DateTime, bound: &DateBound) -> Ordering {
+    match bound {
+        DateBound::Date(d) => {
+            (t.year, t.month, t.day).cmp(&(d.year(), u8::from(d.month()) as i32, d.day() as i32))
+        }
+        DateBound::DateTime(b) => instant_key(t).cmp(&instant_key(b)),
+    }
+}
+
+fn has_tag(link: &Link, needle: &str, aliases: &BTreeMap<String, String>) -> bool {
+    let needle = resolve_tag(aliases, needle);
+    link.tags
+        .iter()
+        .any(|t| tag_matches(&resolve_tag(aliases, t), &needle))
+}
+
+fn collect_tags<S: Into<String>, I: IntoIterator<Item = S>>(tags: I) -> Vec<String> {
+    tags.into_iter()
+        .map(|t| t.into().trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| Error::Validation(format!("invalid regex {pattern:?}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, TextField};
+    use crate::error::Error;
+    use crate::linkleaf_proto::{DateTime, Link, Summary, Via};
+    use time::macros::date;
+
+    fn link(title: &str, tags: &[&str], day: i32, hours: i32) -> Link {
+        let dt = DateTime {
+            year: 2025,
+            month: 3,
+            day,
+            hours,
+            ..Default::default()
+        };
+        Link {
+            id: title.to_lowercase(),
+            title: title.into(),
+            url: format!("https://example.com/{}", title.to_lowercase()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            datetime: Some(dt),
+            created: Some(dt),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tag_sets() {
+        let l = link("Tokio", &["Rust", "async"], 1, 0);
+        assert!(Query::any_tag(["go", "rust"]).matches(&l));
+        assert!(Query::all_tags(["rust", "ASYNC"]).matches(&l));
+        assert!(!Query::all_tags(["rust", "db"]).matches(&l));
+        assert!(Query::no_tags(["go"]).matches(&l));
+        assert!(!Query::no_tags(["async"]).matches(&l));
+    }
+
+    #[test]
+    fn date_ranges_compare_whole_days_for_dates() {
+        let early = link("Early", &[], 10, 0);
+        let late = link("Late", &[], 10, 23);
+
+        // Both are "on" March 10th regardless of time of day.
+        assert!(Query::on(date!(2025 - 03 - 10)).matches(&early));
+        assert!(Query::on(date!(2025 - 03 - 10)).matches(&late));
+        assert!(!Query::on(date!(2025 - 03 - 11)).matches(&late));
+
+        // before/after are exclusive of the bound day.
+        assert!(!Query::before(date!(2025 - 03 - 10)).matches(&early));
+        assert!(Query::before(date!(2025 - 03 - 11)).matches(&late));
+        assert!(Query::after(date!(2025 - 03 - 09)).matches(&early));
+        assert!(!Query::after(date!(2025 - 03 - 10)).matches(&late));
+
+        // Datetime bounds compare down to the time of day.
+        let noon = DateTime {
+            year: 2025,
+            month: 3,
+            day: 10,
+            hours: 12,
+            ..Default::default()
+        };
+        assert!(Query::before(noon).matches(&early));
+        assert!(Query::after(noon).matches(&late));
+        assert!(Query::between(date!(2025 - 03 - 01), noon).matches(&early));
+        assert!(!Query::between(date!(2025 - 03 - 01), noon).matches(&late));
+    }
+
+    #[test]
+    fn text_and_via_predicates() -> Result<(), Error> {
+        let mut l = link("Tokio Runtime", &[], 1, 0);
+        l.summary = Some(Summary::new("Async I/O for Rust"));
+        l.via = Some(Via::new("https://news.ycombinator.com/item?id=1"));
+
+        assert!(Query::title_contains("runtime").matches(&l));
+        assert!(Query::summary_contains("i/o").matches(&l));
+        assert!(Query::url_contains("EXAMPLE.com").matches(&l));
+        assert!(Query::text_contains("rust").matches(&l));
+        assert!(Query::matches_regex(TextField::Title, r"^Tokio\s")?.matches(&l));
+        assert!(Query::via_contains("ycombinator").matches(&l));
+        assert!(Query::via_matches(r"item\?id=\d+")?.matches(&l));
+
+        assert!(Query::fuzzy("tokoi").matches(&l));
+        assert!(!Query::fuzzy("tokoi python").matches(&l));
+
+        l.via = None;
+        assert!(!Query::via_contains("").matches(&l));
+
+        assert!(matches!(
+            Query::matches_regex(TextField::Url, "("),
+            Err(Error::Validation(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn boolean_combinations() {
+        let l = link("Tokio", &["rust"], 1, 0);
+        let rust = Query::any_tag(["rust"]);
+        let go = Query::any_tag(["go"]);
+
+        assert!(rust.clone().and(Query::title_contains("tok")).matches(&l));
+        assert!(!rust.clone().and(go.clone()).matches(&l));
+        assert!(go.clone().or(rust.clone()).matches(&l));
+        assert!((!go).matches(&l));
+        assert!(!(!rust).matches(&l));
+        assert!(Query::And(vec![]).matches(&l));
+        assert!(!Query::Or(vec![]).matches(&l));
+    }
+}
@@ -0,0 +1,569 @@
+//! Ranked full-text search over link titles, summaries, tags and URLs.
+//!
+//! [`SearchIndex`] is an in-memory inverted index scored with BM25. It is
+//! persisted next to the feed at [`index_path`] and kept in sync
+//! incrementally: every link is fingerprinted, and only links whose indexed
+//! text changed are re-tokenized. The index is a cache. Deleting it is always
+//! safe; [`search`] rebuilds it from the feed.
+//!
+//! ## Matching
+//! - Text is split on anything that is not alphanumeric and lowercased.
+//! - Every query term must match (AND). A term matches an indexed term that is
+//!   equal to it or starts with it; prefix-only matches score less.
+//! - Title terms weigh more than tag terms, which weigh more than summary and
+//!   URL terms.
+//! - Trashed links are not indexed.
+
+use crate::error::{Error, Result};
+use crate::fs::{read_feed, write_atomic};
+use crate::linkleaf_proto::{Feed, IndexedDoc, Link, SearchIndexFile, TermCount};
+use prost::Message;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Format version written to [`SearchIndexFile::version`].
+const FORMAT_VERSION: u32 = 1;
+
+const TITLE_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 2;
+const TEXT_WEIGHT: u32 = 1;
+
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+/// Score multiplier for a query term that only matches as a prefix.
+const PREFIX_FACTOR: f32 = 0.5;
+
+/// Where the search index of the feed at `feed_path` is stored: the feed's
+/// file name with `.idx` appended (`links.pb` → `links.pb.idx`).
+pub fn index_path<P: AsRef<Path>>(feed_path: P) -> PathBuf {
+    let mut p = feed_path.as_ref().as_os_str().to_owned();
+    p.push(".idx");
+    PathBuf::from(p)
+}
+
+#[derive(Debug, Clone)]
+struct Doc {
+    id: String,
+    fingerprint: u64,
+    len: u32,
+    terms: Vec<(String, u32)>,
+}
+
+/// Inverted index over the links of one feed.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    /// Documents by slot; `None` marks a free slot.
+    docs: Vec<Option<Doc>>,
+    slots: HashMap<String, usize>,
+    free: Vec<usize>,
+    /// Term -> (slot, weighted term frequency). Ordered for prefix scans.
+    postings: BTreeMap<String, Vec<(usize, u32)>>,
+    total_len: u64,
+}
+
+impl SearchIndex {
+    /// Index every link of `feed` that is not in the trash.
+    pub fn build(feed: &Feed) -> Self {
+        let mut index = SearchIndex::default();
+        index.sync(feed);
+        index
+    }
+
+    /// Number of indexed links.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// `true` if no link is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// `true` if the link with `id` is indexed.
+    pub fn contains(&self, id: &str) -> bool {
+        self.slots.contains_key(id)
+    }
+
+    /// Index `link`, replacing its previous entry.
+    ///
+    /// ## Returns
+    /// `false` if the link was already indexed with the same text.
+    pub fn upsert(&mut self, link: &Link) -> bool {
+        let fingerprint = fingerprint(link);
+        if let Some(&slot) = self.slots.get(&link.id) {
+            if self.docs[slot].as_ref().map(|d| d.fingerprint) == Some(fingerprint) {
+                return false;
+            }
+            self.remove(&link.id);
+        }
+        self.insert(Doc::from_terms(
+            link.id.clone(),
+            fingerprint,
+            weighted_terms(link),
+        ));
+        true
+    }
+
+    /// Drop the link with `id` from the index.
+    ///
+    /// ## Returns
+    /// `true` if it was indexed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.slots.remove(id) else {
+            return false;
+        };
+        let doc = self.docs[slot]
+            .take()
+            .expect("slot map points at a live doc");
+        for (term, _) in &doc.terms {
+            if let Some(list) = self.postings.get_mut(term) {
+                list.retain(|(s, _)| *s != slot);
+                if list.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_len -= u64::from(doc.len);
+        self.free.push(slot);
+        true
+    }
+
+    /// Bring the index in line with `feed`: index new and changed links and
+    /// drop links that were removed or trashed.
+    ///
+    /// ## Returns
+    /// `true` if anything changed.
+    pub fn sync(&mut self, feed: &Feed) -> bool {
+        let live: HashSet<&str> = feed
+            .links
+            .iter()
+            .filter(|l| l.deleted.is_none())
+            .map(|l| l.id.as_str())
+            .collect();
+
+        let stale: Vec<String> = self
+            .slots
+            .keys()
+            .filter(|id| !live.contains(id.as_str()))
+            .cloned()
+            .collect();
+        let mut changed = !stale.is_empty();
+        for id in stale {
+            self.remove(&id);
+        }
+        for link in feed.links.iter().filter(|l| l.deleted.is_none()) {
+            changed |= self.upsert(link);
+        }
+        changed
+    }
+
+    /// Rank the links of `feed` against `query`.
+    ///
+    /// ## Returns
+    /// Matching links with their BM25 score, best first. Ties keep the feed's
+    /// stored order. An empty query matches nothing.
+    pub fn search<'a>(&self, feed: &'a Feed, query: &str) -> Vec<(&'a Link, f32)> {
+        let scores = self.scores(query);
+        let mut hits: Vec<(usize, &Link, f32)> = feed
+            .links
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.deleted.is_none())
+            .filter_map(|(pos, l)| {
+                let slot = self.slots.get(&l.id)?;
+                scores.get(slot).map(|&s| (pos, l, s))
+            })
+            .collect();
+        hits.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(&b.0)));
+        hits.into_iter().map(|(_, l, s)| (l, s)).collect()
+    }
+
+    /// Read a persisted index.
+    ///
+    /// ## Errors
+    /// - [`Error::NotFound`] / [`Error::Io`] if the file can't be read.
+    /// - [`Error::Decode`] if it is not a valid index.
+    /// - [`Error::Validation`] if it was written by an unknown format version.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+        let file = SearchIndexFile::decode(&*bytes).map_err(|source| Error::Decode {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if file.version != FORMAT_VERSION {
+            return Err(Error::Validation(format!(
+                "unsupported search index version {} in {}",
+                file.version,
+                path.display()
+            )));
+        }
+
+        let mut index = SearchIndex::default();
+        for doc in file.docs {
+            let terms = doc.terms.into_iter().map(|t| (t.term, t.count)).collect();
+            index.insert(Doc::from_terms(doc.id, doc.fingerprint, terms));
+        }
+        Ok(index)
+    }
+
+    /// Persist the index atomically to `path`.
+    ///
+    /// ## Errors
+    /// [`Error::Encode`] or I/O errors.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = SearchIndexFile {
+            version: FORMAT_VERSION,
+            docs: self
+                .docs
+                .iter()
+                .flatten()
+                .map(|d| IndexedDoc {
+                    id: d.id.clone(),
+                    fingerprint: d.fingerprint,
+                    terms: d
+                        .terms
+                        .iter()
+                        .map(|(term, count)| TermCount {
+                            term: term.clone(),
+                            count: *count,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        };
+        let mut buf = Vec::with_capacity(file.encoded_len());
+        file.encode(&mut buf)
+            .map_err(|source| Error::Encode { source })?;
+        write_atomic(path.as_ref(), &buf)
+    }
+
+    fn insert(&mut self, doc: Doc) {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.docs.push(None);
+                self.docs.len() - 1
+            }
+        };
+        for (term, count) in &doc.terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .push((slot, *count));
+        }
+        self.total_len += u64::from(doc.len);
+        self.slots.insert(doc.id.clone(), slot);
+        self.docs[slot] = Some(doc);
+    }
+
+    /// BM25 score per slot for documents matching every query term.
+    fn scores(&self, query: &str) -> HashMap<usize, f32> {
+        let mut terms: Vec<String> = tokens(query).collect();
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() || self.is_empty() {
+            return HashMap::new();
+        }
+
+        let n = self.len() as f32;
+        let avg_len = self.total_len as f32 / n;
+        let mut total: Option<HashMap<usize, f32>> = None;
+
+        for q in &terms {
+            // Best contribution of this query term per document, over the
+            // exact term and all of its prefix expansions.
+            let mut best: HashMap<usize, f32> = HashMap::new();
+            for (term, list) in self
+                .postings
+                .range(q.clone()..)
+                .take_while(|(t, _)| t.starts_with(q.as_str()))
+            {
+                let df = list.len() as f32;
+                let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                let factor = if term == q { 1.0 } else { PREFIX_FACTOR };
+                for &(slot, tf) in list {
+                    let len = self.docs[slot].as_ref().map_or(0, |d| d.len) as f32;
+                    let tf = tf as f32;
+                    let norm = tf * (BM25_K1 + 1.0)
+                        / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len));
+                    let s = factor * idf * norm;
+                    let e = best.entry(slot).or_insert(0.0);
+                    *e = e.max(s);
+                }
+            }
+
+            total = Some(match total {
+                None => best,
+                Some(mut acc) => {
+                    acc.retain(|slot, score| match best.get(slot) {
+                        Some(s) => {
+                            *score += s;
+                            true
+                        }
+                        None => false,
+                    });
+                    acc
+                }
+            });
+        }
+        total.unwrap_or_default()
+    }
+}
+
+impl Doc {
+    fn from_terms(id: String, fingerprint: u64, terms: Vec<(String, u32)>) -> Self {
+        let len = terms.iter().map(|(_, c)| c).sum();
+        Doc {
+            id,
+            fingerprint,
+            len,
+            terms,
+        }
+    }
+}
+
+/// Search the feed at `file` for `query`.
+///
+/// ## Behavior
+/// - Reads the feed and the index at [`index_path`]. A missing, corrupt or
+///   outdated index is rebuilt or brought up to date from the feed.
+/// - If the index changed, it is written back (best effort: failing to
+///   persist it does not fail the search).
+///
+/// ## Returns
+/// Matching links with their score, best first (see [`SearchIndex::search`]).
+///
+/// ## Errors
+/// Any error from [`read_feed`].
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::search;
+///
+/// for (link, score) in search("mylinks.pb", "tokio runt")? {
+///     println!("{score:>6.2}  {}", link.title);
+/// }
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+pub fn search<P: AsRef<Path>>(file: P, query: &str) -> Result<Vec<(Link, f32)>> {
+    let file = file.as_ref();
+    let feed = read_feed(file)?;
+    let index = open_index(file, &feed);
+    Ok(index
+        .search(&feed, query)
+        .into_iter()
+        .map(|(l, s)| (l.clone(), s))
+        .collect())
+}
+
+/// Load the index of `file` and sync it with `feed`, rebuilding it if it
+/// can't be loaded. Writes it back if anything changed.
+fn open_index(file: &Path, feed: &Feed) -> SearchIndex {
+    let path = index_path(file);
+    let (mut index, mut changed) = match SearchIndex::load(&path) {
+        Ok(index) => (index, false),
+        Err(_err) => {
+            #[cfg(feature = "logs")]
+            if !_err.is_not_found() {
+                tracing::warn!(error = %_err, path = %path.display(), "rebuilding search index");
+            }
+            (SearchIndex::default(), true)
+        }
+    };
+    changed |= index.sync(feed);
+    if changed {
+        save_best_effort(&index, &path);
+    }
+    index
+}
+
+/// Called after the feed at `file` was written: bring an existing index up to
+/// date. Feeds that were never searched get no index file.
+pub(crate) fn refresh_existing_index(file: &Path, feed: &Feed) {
+    let path = index_path(file);
+    if !path.exists() {
+        return;
+    }
+    // An unreadable index is rebuilt lazily by the next `search`.
+    if let Ok(mut index) = SearchIndex::load(&path)
+        && index.sync(feed)
+    {
+        save_best_effort(&index, &path);
+    }
+}
+
+fn save_best_effort(index: &SearchIndex, path: &Path) {
+    if let Err(_err) = index.save(path) {
+        #[cfg(feature = "logs")]
+        tracing::warn!(error = %_err, path = %path.display(), "failed to save search index");
+    }
+}
+
+/// Lowercased alphanumeric runs of `text`.
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+}
+
+fn weighted_terms(link: &Link) -> Vec<(String, u32)> {
+    let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+    let mut add = |text: &str, weight: u32| {
+        for t in tokens(text) {
+            *counts.entry(t).or_default() += weight;
+        }
+    };
+    add(&link.title, TITLE_WEIGHT);
+    for tag in &link.tags {
+        add(tag, TAG_WEIGHT);
+    }
+    if let Some(summary) = &link.summary {
+        add(&summary.content, TEXT_WEIGHT);
+    }
+    add(&link.url, TEXT_WEIGHT);
+    counts.into_iter().collect()
+}
+
+/// FNV-1a over the indexed fields, so the fingerprint is stable across
+/// builds and platforms.
+fn fingerprint(link: &Link) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for b in bytes.iter().chain(&[0xff]) {
+            h ^= u64::from(*b);
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    feed(link.title.as_bytes());
+    feed(link.url.as_bytes());
+    feed(
+        link.summary
+            .as_ref()
+            .map_or(&[][..], |s| s.content.as_bytes()),
+    );
+    for tag in &link.tags {
+        feed(tag.as_bytes());
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchIndex, index_path, search};
+    use crate::fs::write_feed;
+    use crate::linkleaf_proto::{DateTime, Feed, Link, Summary};
+    use crate::{LinkSelector, add, trash};
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    fn link(id: &str, title: &str, tags: &[&str], summary: &str) -> Link {
+        Link {
+            id: id.into(),
+            title: title.into(),
+            url: format!("https://example.com/{id}"),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            summary: Some(Summary::new(summary)),
+            datetime: Some(DateTime::default()),
+            ..Default::default()
+        }
+    }
+
+    fn feed(links: Vec<Link>) -> Feed {
+        Feed {
+            version: 1,
+            links,
+            ..Default::default()
+        }
+    }
+
+    fn ids(hits: &[(&Link, f32)]) -> Vec<String> {
+        hits.iter().map(|(l, _)| l.id.clone()).collect()
+    }
+
+    #[test]
+    fn ranks_title_matches_above_summary_matches() {
+        let f = feed(vec![
+            link("a", "Databases", &[], "notes on tokio"),
+            link("b", "Tokio tutorial", &["rust"], ""),
+            link("c", "Go channels", &["go"], ""),
+        ]);
+        let index = SearchIndex::build(&f);
+
+        let hits = index.search(&f, "TOKIO");
+        assert_eq!(ids(&hits), vec!["b", "a"]);
+        assert!(hits[0].1 > hits[1].1);
+
+        // All terms must match; tags are searchable.
+        assert_eq!(ids(&index.search(&f, "tokio rust")), vec!["b"]);
+        assert!(index.search(&f, "").is_empty());
+        assert!(index.search(&f, "python").is_empty());
+    }
+
+    #[test]
+    fn prefix_matches_score_below_exact_matches() {
+        let f = feed(vec![
+            link("a", "Runtime internals", &[], ""),
+            link("b", "Run faster", &[], ""),
+        ]);
+        let index = SearchIndex::build(&f);
+
+        let hits = index.search(&f, "run");
+        assert_eq!(ids(&hits), vec!["b", "a"]);
+        assert_eq!(ids(&index.search(&f, "runt")), vec!["a"]);
+    }
+
+    #[test]
+    fn sync_reindexes_only_changed_links() {
+        let mut f = feed(vec![
+            link("a", "Alpha", &[], ""),
+            link("b", "Beta", &[], ""),
+        ]);
+        let mut index = SearchIndex::build(&f);
+        assert!(!index.sync(&f), "nothing changed");
+
+        f.links[0].title = "Gamma".into();
+        f.links[1].deleted = Some(DateTime::default());
+        assert!(index.sync(&f));
+        assert_eq!(index.len(), 1);
+        assert!(!index.contains("b"), "trashed links are dropped");
+        assert!(index.search(&f, "alpha").is_empty());
+        assert_eq!(ids(&index.search(&f, "gamma")), vec!["a"]);
+
+        assert!(index.remove("a"));
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn search_persists_index_and_commits_keep_it_fresh() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        write_feed(&file, feed(vec![link("a", "Tokio tutorial", &[], "")]))?;
+
+        assert!(!index_path(&file).exists());
+        assert_eq!(search(&file, "tokio")?.len(), 1);
+        assert!(index_path(&file).exists());
+
+        let added = add(
+            &file,
+            "Tokio console",
+            "https://c/",
+            None,
+            ["rust"],
+            None,
+            None,
+        )?;
+        let on_disk = SearchIndex::load(index_path(&file))?;
+        assert!(on_disk.contains(&added.id), "add updated the saved index");
+
+        trash(&file, LinkSelector::id(added.id.clone()))?;
+        assert!(!SearchIndex::load(index_path(&file))?.contains(&added.id));
+
+        // A corrupt index is rebuilt transparently.
+        std::fs::write(index_path(&file), b"\xff\xff\xff")?;
+        let hits = search(&file, "tokio")?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.id, "a");
+        Ok(())
+    }
+}
@@ -0,0 +1,1713 @@
+//! In-memory handle over a feed file for batched edits.
+//!
+//! [`FeedStore`] reads a feed once, lets callers apply any number of edits to
+//! the in-memory [`Feed`], and writes it back in a single atomic
+//! [`write_feed`](crate::fs::write_feed) on [`FeedStore::commit`] (or when dropped).
+
+use crate::canonical::{CanonicalOptions, canonicalize_url_with};
+use crate::check::{self, Issue};
+use crate::clock::{Clock, SystemClock};
+use crate::dedup::{self, DedupOptions, MergedGroup};
+use crate::error::{Error, Result};
+use crate::fs::{FeedLock, LockOptions, decode_feed, write_feed_if_unchanged_locked};
+use crate::linkleaf_proto::{DateTime, Feed, Link, Summary, Via};
+use crate::migrations::{self, CURRENT_VERSION};
+use crate::query::Query;
+use crate::tags::{self, TagNode, TagPolicy, same_tag};
+use crate::validation::{LinkRules, Strictness, validate_link};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Selects the links an operation such as [`remove`](crate::remove) applies to.
+pub enum LinkSelector {
+    /// The link whose `id` equals this value.
+    Id(String),
+    /// Every link whose `url` equals this value exactly.
+    Url(String),
+    /// Every link for which the predicate returns `true`.
+    Predicate(Box<dyn Fn(&Link) -> bool>),
+}
+
+impl LinkSelector {
+    /// Select by id.
+    pub fn id<S: Into<String>>(id: S) -> Self {
+        LinkSelector::Id(id.into())
+    }
+
+    /// Select by exact URL.
+    pub fn url<S: Into<String>>(url: S) -> Self {
+        LinkSelector::Url(url.into())
+    }
+
+    /// Select with an arbitrary predicate.
+    pub fn predicate<F>(f: F) -> Self
+    where
+        F: Fn(&Link) -> bool + 'static,
+    {
+        LinkSelector::Predicate(Box::new(f))
+    }
+
+    /// `true` if `link` is selected.
+    pub fn matches(&self, link: &Link) -> bool {
+        match self {
+            LinkSelector::Id(id) => &link.id == id,
+            LinkSelector::Url(url) => &link.url == url,
+            LinkSelector::Predicate(f) => f(link),
+        }
+    }
+}
+
+impl From<Uuid> for LinkSelector {
+    fn from(id: Uuid) -> Self {
+        LinkSelector::Id(id.to_string())
+    }
+}
+
+impl fmt::Debug for LinkSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkSelector::Id(id) => f.debug_tuple("Id").field(id).finish(),
+            LinkSelector::Url(url) => f.debug_tuple("Url").field(url).finish(),
+            LinkSelector::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+impl fmt::Display for LinkSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkSelector::Id(id) => write!(f, "id {id}"),
+            LinkSelector::Url(url) => write!(f, "url {url}"),
+            LinkSelector::Predicate(_) => f.write_str("predicate"),
+        }
+    }
+}
+
+/// A partial update for a single [`Link`], applied with [`update`](crate::update)
+/// or [`FeedStore::update`].
+///
+/// Every field left as `None` (or empty) keeps the link's current value, so a
+/// patch only touches what it names.
+///
+/// ## Tag operations
+/// Applied in order: `tags` replaces the whole list, then `remove_tags` drops
+/// matching tags, then `add_tags` appends tags that are not present yet.
+/// Tags are compared folded (see [`crate::tags`]). If the patch touches tags,
+/// the resulting list is normalized with [`LinkPatch::tag_policy`](#structfield.tag_policy).
+///
+/// The patched link must pass [`LinkPatch::validation`](#structfield.validation),
+/// otherwise it is left unchanged.
+///
+/// Patches compare equal if everything but their [`clock`](#structfield.clock)
+/// is equal.
+///
+/// ## Example
+/// ```
+/// use linkleaf_core::LinkPatch;
+/// use linkleaf_core::linkleaf_proto::Summary;
+///
+/// let patch = LinkPatch::new()
+///     .title("Tokio • Async Rust")
+///     .summary(Some(Summary::new("runtime")))
+///     .add_tag("async")
+///     .remove_tag("draft")
+///     .preserve_position(true);
+/// assert!(patch.url.is_none()); // url is left alone
+/// ```
+#[derive(Debug, Clone)]
+pub struct LinkPatch {
+    /// New title.
+    pub title: Option<String>,
+    /// New URL.
+    pub url: Option<String>,
+    /// New summary; `Some(None)` clears it.
+    pub summary: Option<Option<Summary>>,
+    /// New via; `Some(None)` clears it.
+    pub via: Option<Option<Via>>,
+    /// Replace the whole tag list.
+    pub tags: Option<Vec<String>>,
+    /// Tags to append if missing.
+    pub add_tags: Vec<String>,
+    /// Tags to remove if present.
+    pub remove_tags: Vec<String>,
+    /// Keep the link where it is. By default an update moves the link to the
+    /// front, like [`add`](crate::add). Either way `created` and `datetime`
+    /// are kept and `updated` is set to the current time.
+    pub preserve_position: bool,
+    /// How the link's tags are normalized when the patch touches them.
+    /// Defaults to [`TagPolicy::default`].
+    pub tag_policy: TagPolicy,
+    /// Rules the patched link must pass. Defaults to
+    /// [`Strictness::Required`], like [`AddOptions::validation`].
+    pub validation: LinkRules,
+    /// Where `updated` comes from. `None` (the default) uses the store's
+    /// clock, like [`AddOptions::clock`].
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+impl PartialEq for LinkPatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.title == other.title
+            && self.url == other.url
+            && self.summary == other.summary
+            && self.via == other.via
+            && self.tags == other.tags
+            && self.add_tags == other.add_tags
+            && self.remove_tags == other.remove_tags
+            && self.preserve_position == other.preserve_position
+            && self.tag_policy == other.tag_policy
+            && self.validation == other.validation
+    }
+}
+
+impl Default for LinkPatch {
+    fn default() -> Self {
+        LinkPatch {
+            title: None,
+            url: None,
+            summary: None,
+            via: None,
+            tags: None,
+            add_tags: Vec::new(),
+            remove_tags: Vec::new(),
+            preserve_position: false,
+            tag_policy: TagPolicy::default(),
+            validation: LinkRules {
+                strictness: Strictness::Required,
+                ..Default::default()
+            },
+            clock: None,
+        }
+    }
+}
+
+impl LinkPatch {
+    /// An empty patch that changes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the title.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the URL.
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Set (`Some`) or clear (`None`) the summary.
+    pub fn summary(mut self, summary: Option<Summary>) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Set (`Some`) or clear (`None`) the via.
+    pub fn via(mut self, via: Option<Via>) -> Self {
+        self.via = Some(via);
+        self
+    }
+
+    /// Replace all tags.
+    pub fn tags<S, T>(mut self, tags: T) -> Self
+    where
+        S: Into<String>,
+        T: IntoIterator<Item = S>,
+    {
+        self.tags = Some(tags.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Append a tag if it is not present yet.
+    pub fn add_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.add_tags.push(tag.into());
+        self
+    }
+
+    /// Remove a tag if present.
+    pub fn remove_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.remove_tags.push(tag.into());
+        self
+    }
+
+    /// Keep the link's position (see [`LinkPatch::preserve_position`](#structfield.preserve_position)).
+    pub fn preserve_position(mut self, preserve: bool) -> Self {
+        self.preserve_position = preserve;
+        self
+    }
+
+    /// Set the tag normalization policy.
+    pub fn tag_policy(mut self, policy: TagPolicy) -> Self {
+        self.tag_policy = policy;
+        self
+    }
+
+    /// Set the rules the patched link must pass.
+    pub fn validation(mut self, rules: LinkRules) -> Self {
+        self.validation = rules;
+        self
+    }
+
+    /// Take `updated` from `clock` instead of the store's clock.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Apply the field and tag changes of this patch to `link`.
+    ///
+    /// Position and timestamps are handled by the caller.
+    ///
+    /// ## Errors
+    /// [`Error::Validation`] if the patched link breaks
+    /// [`LinkPatch::validation`](#structfield.validation); `link` is left
+    /// unchanged.
+    pub fn apply_to(&self, link: &mut Link) -> Result<()> {
+        self.apply_with_aliases(link, &BTreeMap::new())
+    }
+
+    /// [`LinkPatch::apply_to`], resolving the feed's tag aliases when
+    /// normalizing tags.
+    pub(crate) fn apply_with_aliases(
+        &self,
+        link: &mut Link,
+        aliases: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut patched = link.clone();
+        self.apply_fields(&mut patched, aliases);
+        validate_link(&patched, &self.validation)?;
+        *link = patched;
+        Ok(())
+    }
+
+    fn apply_fields(&self, link: &mut Link, aliases: &BTreeMap<String, String>) {
+        if let Some(title) = &self.title {
+            link.title = title.clone();
+        }
+        if let Some(url) = &self.url {
+            link.url = url.clone();
+        }
+        if let Some(summary) = &self.summary {
+            link.summary = summary.clone();
+        }
+        if let Some(via) = &self.via {
+            link.via = via.clone();
+        }
+        if self.tags.is_none() && self.add_tags.is_empty() && self.remove_tags.is_empty() {
+            return;
+        }
+        if let Some(tags) = &self.tags {
+            link.tags = tags.clone();
+        }
+        link.tags
+            .retain(|t| !self.remove_tags.iter().any(|r| same_tag(t, r)));
+        for tag in &self.add_tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !link.tags.iter().any(|t| same_tag(t, tag)) {
+                link.tags.push(tag.to_string());
+            }
+        }
+        link.tags = self.tag_policy.normalize_with_aliases(&link.tags, aliases);
+    }
+}
+
+/// Options for [`add_with_options`](crate::add_with_options) and
+/// [`FeedStore::upsert_with`].
+///
+/// Options compare equal if everything but their [`clock`](AddOptions::clock)
+/// is equal.
+#[derive(Debug, Clone)]
+pub struct AddOptions {
+    /// Move an updated link to the front of the feed. New links always go to
+    /// the front. Defaults to `true`.
+    pub move_to_front: bool,
+    /// When no id is given, find the link to update by comparing URLs in
+    /// this canonical form. `None` compares URLs exactly. Defaults to
+    /// [`CanonicalOptions::default`].
+    pub canonical: Option<CanonicalOptions>,
+    /// Rules the added link must pass (see [`crate::validation::validate_link`]).
+    /// Defaults to [`Strictness::Required`]: title and URL must not be empty.
+    /// [`Strictness::Strict`] also checks URL syntax, lengths and tags, and
+    /// expects `via` to be a URL, which older feeds may not follow.
+    pub validation: LinkRules,
+    /// How the link's tags are normalized before they are stored. Defaults
+    /// to [`TagPolicy::default`]; [`TagPolicy::preserve`] stores them as given.
+    pub tags: TagPolicy,
+    /// Where `created`/`updated` come from. `None` (the default) uses the
+    /// store's clock, a [`SystemClock`] unless set with
+    /// [`FeedStore::with_clock`].
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+impl Default for AddOptions {
+    fn default() -> Self {
+        AddOptions {
+            move_to_front: true,
+            canonical: Some(CanonicalOptions::default()),
+            validation: LinkRules {
+                strictness: Strictness::Required,
+                ..Default::default()
+            },
+            tags: TagPolicy::default(),
+            clock: None,
+        }
+    }
+}
+
+impl PartialEq for AddOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.move_to_front == other.move_to_front
+            && self.canonical == other.canonical
+            && self.validation == other.validation
+            && self.tags == other.tags
+    }
+}
+
+impl Eq for AddOptions {}
+
+/// Options for [`trash_with`](crate::trash_with).
+#[derive(Debug, Clone, Default)]
+pub struct TrashOptions {
+    /// Where the `deleted` tombstone comes from. `None` (the default) uses a
+    /// [`SystemClock`].
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+/// An open feed file with in-memory batching.
+///
+/// ## Behavior
+/// - [`FeedStore::open`] takes the feed's [`FeedLock`] and keeps it until the
+///   store is dropped, so no other process using the lock can write the file
+///   in between.
+/// - Timestamps come from its [`Clock`] ([`SystemClock`] unless set with
+///   [`FeedStore::with_clock`]).
+/// - Reads the feed once, upgrading older formats (see [`crate::migrations`]);
+///   an upgraded feed starts out dirty, so the upgrade is written with the
+///   next commit. A missing file starts a new feed (`version =`
+///   [`CURRENT_VERSION`]).
+/// - Mutating methods only touch memory and mark the store dirty. They also
+///   refresh the lock now and then, so a store kept open for a long time does
+///   not look stale while it is being edited.
+/// - [`FeedStore::commit`] bumps `revision` and persists via
+///   [`write_feed_if_unchanged_locked`] if anything changed. If the lock was
+///   broken anyway and someone else wrote the file, it fails with
+///   [`Error::Conflict`] instead of overwriting their changes.
+/// - Dropping a dirty store commits too, unless the thread is panicking (a
+///   half-applied batch is discarded). Errors on drop are ignored (logged with
+///   the `logs` feature), so prefer an explicit `commit`.
+///
+/// ## Example
+/// ```no_run
+/// use linkleaf_core::FeedStore;
+///
+/// let mut store = FeedStore::open("mylinks.pb")?;
+/// for (title, url) in [("Tokio", "https://tokio.rs/"), ("Serde", "https://serde.rs/")] {
+///     store.upsert(title, url, None, ["rust"], None, None)?;
+/// }
+/// store.commit()?; // one write for the whole batch
+/// Ok::<(), linkleaf_core::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FeedStore {
+    path: PathBuf,
+    feed: Feed,
+    dirty: bool,
+    lock: FeedLock,
+    /// Refresh the lock on mutation when it is older than this.
+    refresh_every: Duration,
+    refreshed: Instant,
+    clock: Arc<dyn Clock>,
+    /// Built on the first upsert without an id; `None` after edits that
+    /// reorder or rewrite links in bulk.
+    url_index: Option<UrlIndex>,
+}
+
+impl FeedStore {
+    /// Open the feed at `path` with default lock options.
+    ///
+    /// ## Errors
+    /// - [`Error::Lock`] if the lock cannot be acquired.
+    /// - Any error from [`read_feed`](crate::fs::read_feed) other than
+    ///   [`Error::NotFound`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<FeedStore> {
+        Self::open_with(path, &LockOptions::default())
+    }
+
+    /// Open the feed at `path`, acquiring its lock with `lock_options`.
+    pub fn open_with<P: AsRef<Path>>(path: P, lock_options: &LockOptions) -> Result<FeedStore> {
+        let path = path.as_ref();
+        let lock = FeedLock::acquire_with(path, lock_options)?;
+        let (feed, migrated) = match decode_feed(path) {
+            Ok(mut f) => {
+                let migrated = !migrations::migrate(&mut f)?.is_empty();
+                (f, migrated)
+            }
+            Err(err) if err.is_not_found() => (
+                Feed {
+                    version: CURRENT_VERSION,
+                    ..Default::default()
+                },
+                false,
+            ),
+            Err(err) => return Err(err),
+        };
+
+        Ok(FeedStore {
+            path: path.to_path_buf(),
+            feed,
+            // Persist an upgraded format with the next commit.
+            dirty: migrated,
+            lock,
+            refresh_every: lock_options.stale_after / 4,
+            refreshed: Instant::now(),
+            clock: Arc::new(SystemClock),
+            url_index: None,
+        })
+    }
+
+    /// Open like [`FeedStore::open`], without counting a format upgrade as a
+    /// change: the one-shot functions of the crate root write it along with
+    /// their edit and leave the file untouched when they change nothing.
+    pub(crate) fn open_for_edit<P: AsRef<Path>>(path: P) -> Result<FeedStore> {
+        let mut store = Self::open(path)?;
+        store.dirty = false;
+        Ok(store)
+    }
+
+    /// Take timestamps from `clock` instead of the [`SystemClock`].
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use linkleaf_core::FeedStore;
+    /// use linkleaf_core::clock::FixedClock;
+    /// use linkleaf_core::linkleaf_proto::DateTime;
+    ///
+    /// let at = DateTime::parse("2025-03-14T09:00:00Z")?;
+    /// let mut store = FeedStore::open("mylinks.pb")?.with_clock(FixedClock(at.clone()));
+    /// let link = store.upsert("Tokio", "https://tokio.rs/", None, ["rust"], None, None)?;
+    /// assert_eq!(link.created, Some(at));
+    /// Ok::<(), linkleaf_core::Error>(())
+    /// ```
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Path of the underlying `.pb` file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The in-memory feed, including uncommitted changes.
+    pub fn feed(&self) -> &Feed {
+        &self.feed
+    }
+
+    /// `true` if there are changes that have not been committed yet.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Look up a link by id.
+    pub fn get(&self, id: &str) -> Option<&Link> {
+        self.feed.links.iter().find(|l| l.id == id)
+    }
+
+    /// Iterate over the links in stored (newest-first) order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Link> {
+        self.feed.links.iter()
+    }
+
+    /// Links matching `query`, in stored order. Trashed links are skipped.
+    pub fn query(&self, query: &Query) -> Vec<&Link> {
+        self.feed
+            .links
+            .iter()
+            .filter(|l| {
+                l.deleted.is_none() && query.matches_with_aliases(l, &self.feed.tag_aliases)
+            })
+            .collect()
+    }
+
+    /// Add or update a link in memory, with the same semantics as [`add`](crate::add).
+    ///
+    /// Equivalent to [`FeedStore::upsert_with`] with [`AddOptions::default`].
+    pub fn upsert<S, T>(
+        &mut self,
+        title: S,
+        url: S,
+        summary: Option<Summary>,
+        tags: T,
+        via: Option<Via>,
+        id: Option<Uuid>,
+    ) -> Result<Link>
+    where
+        S: Into<String>,
+        T: IntoIterator<Item = S>,
+    {
+        self.upsert_with(title, url, summary, tags, via, id, &AddOptions::default())
+    }
+
+    /// Add or update a link in memory, with explicit [`AddOptions`].
+    ///
+    /// ## Behavior
+    /// - With an `id`: updates the link with that id, or inserts a new one with it.
+    /// - The link is first checked against [`AddOptions::validation`].
+    /// - Without an `id`: updates the first link with the same (canonical)
+    ///   `url`, or inserts a new one with a fresh UUID v4.
+    /// - A new link gets `created`, `updated` and `datetime` set to the current
+    ///   time of [`AddOptions::clock`] (or the store's clock) and is inserted at
+    ///   the front.
+    /// - An updated link keeps `created` and `datetime`, gets `updated` set to
+    ///   now, and moves to the front only if [`AddOptions::move_to_front`] is set.
+    /// - Updating a trashed link restores it.
+    ///
+    /// ## Errors
+    /// - [`Error::Validation`] if the link breaks [`AddOptions::validation`];
+    ///   the feed is left unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upsert_with<S, T>(
+        &mut self,
+        title: S,
+        url: S,
+        summary: Option<Summary>,
+        tags: T,
+        via: Option<Via>,
+        id: Option<Uuid>,
+        options: &AddOptions,
+    ) -> Result<Link>
+    where
+        S: Into<String>,
+        T: IntoIterator<Item = S>,
+    {
+        let now = options.clock.as_ref().unwrap_or(&self.clock).now();
+
+        let title = title.into();
+        let url = url.into();
+        let tags = options
+            .tags
+            .normalize_with_aliases(tags.into_iter().map(Into::into), &self.feed.tag_aliases);
+        let id_opt: Option<String> = id.map(|u| u.to_string());
+
+        let candidate = Link {
+            title,
+            url,
+            summary,
+            tags,
+            via,
+            ..Default::default()
+        };
+        validate_link(&candidate, &options.validation)?;
+        let Link {
+            title,
+            url,
+            summary,
+            tags,
+            via,
+            ..
+        } = candidate;
+
+        // behavior:
+        // - If `id` provided: update by id; else insert (even if URL duplicates).
+        // - If no `id`: update by (canonical) URL; else insert with fresh UUID.
+        let existing = match &id_opt {
+            Some(uid) => self.feed.links.iter().position(|l| &l.id == uid),
+            None => {
+                let index = match self.url_index.take() {
+                    Some(index) if index.canonical == options.canonical => index,
+                    _ => UrlIndex::build(&self.feed.links, &options.canonical),
+                };
+                let pos = index.first(&url_key(&url, &index.canonical), self.feed.links.len());
+                self.url_index = Some(index);
+                pos
+            }
+        };
+        let old_url = existing.map(|pos| self.feed.links[pos].url.clone());
+
+        let feed = &mut self.feed;
+        let updated_or_new = match existing {
+            Some(pos) => {
+                let item = update_link_in_place(
+                    feed,
+                    pos,
+                    title,
+                    url,
+                    summary,
+                    tags,
+                    via,
+                    now,
+                    options.move_to_front,
+                );
+                #[cfg(feature = "logs")]
+                tracing::info!(id = %item.id, by_id = id_opt.is_some(), "updated existing link");
+                item
+            }
+            None => {
+                let uid = id_opt.unwrap_or_else(|| Uuid::new_v4().to_string());
+                let item = insert_new_link_front(feed, uid, title, url, summary, tags, via, now);
+                #[cfg(feature = "logs")]
+                tracing::info!(id = %item.id, "inserted new link");
+                item
+            }
+        };
+
+        if let Some(index) = &mut self.url_index {
+            let len = self.feed.links.len();
+            let key = url_key(&updated_or_new.url, &index.canonical);
+            match (existing, old_url) {
+                (Some(pos), Some(old_url)) => {
+                    index.remove(&url_key(&old_url, &index.canonical), pos, len);
+                    let to = if options.move_to_front { 0 } else { pos };
+                    index.insert(key, to, len);
+                }
+                _ => index.insert(key, 0, len),
+            }
+        }
+
+        self.mark_dirty();
+        Ok(updated_or_new)
+    }
+
+    /// Remove the link with the given id from memory, returning it.
+    pub fn remove(&mut self, id: &str) -> Option<Link> {
+        let pos = self.feed.links.iter().position(|l| l.id == id)?;
+        let len = self.feed.links.len();
+        let link = self.feed.links.remove(pos);
+        if let Some(index) = &mut self.url_index {
+            index.remove(&url_key(&link.url, &index.canonical), pos, len);
+        }
+        self.mark_dirty();
+        Some(link)
+    }
+
+    /// Remove every link matched by `selector` from memory, returning them in
+    /// their stored order. The store is only marked dirty if something matched.
+    pub fn remove_where(&mut self, selector: &LinkSelector) -> Vec<Link> {
+        let (removed, kept): (Vec<Link>, Vec<Link>) = std::mem::take(&mut self.feed.links)
+            .into_iter()
+            .partition(|l| selector.matches(l));
+        self.feed.links = kept;
+        if !removed.is_empty() {
+            self.url_index = None;
+            self.mark_dirty();
+        }
+        removed
+    }
+
+    /// Apply `patch` to the link with the given id.
+    ///
+    /// The link's `updated` timestamp is set to the current time of
+    /// [`LinkPatch::clock`](LinkPatch#structfield.clock) (or the store's
+    /// clock); its `created` and `datetime` are kept. Unless
+    /// [`LinkPatch::preserve_position`](LinkPatch#structfield.preserve_position)
+    /// is set, it moves to the front.
+    ///
+    /// ## Errors
+    /// - [`Error::LinkNotFound`] if no link has this id.
+    /// - [`Error::Validation`] if the patched link breaks
+    ///   [`LinkPatch::validation`](LinkPatch#structfield.validation); the
+    ///   feed is left unchanged.
+    pub fn update(&mut self, id: &str, patch: &LinkPatch) -> Result<Link> {
+        let Some(pos) = self.feed.links.iter().position(|l| l.id == id) else {
+            return Err(Error::LinkNotFound {
+                path: self.path.clone(),
+                selector: LinkSelector::id(id).to_string(),
+            });
+        };
+
+        let now = patch.clock.as_ref().unwrap_or(&self.clock).now();
+        let link = &mut self.feed.links[pos];
+        patch.apply_with_aliases(link, &self.feed.tag_aliases)?;
+        touch(link, now);
+        let link = link.clone();
+        if !patch.preserve_position {
+            let moved = self.feed.links.remove(pos);
+            self.feed.links.insert(0, moved);
+        }
+        #[cfg(feature = "logs")]
+        tracing::info!(id = %link.id, "patched link");
+
+        self.url_index = None;
+        self.mark_dirty();
+        Ok(link)
+    }
+
+    /// Move every matched link that is not already trashed to the trash,
+    /// stamping its `deleted` tombstone with the store's current time.
+    pub fn trash_where(&mut self, selector: &LinkSelector) -> Vec<Link> {
+        let now = self.clock.now();
+        let mut trashed = Vec::new();
+        for l in &mut self.feed.links {
+            if l.deleted.is_none() && selector.matches(l) {
+                l.deleted = Some(now);
+                trashed.push(l.clone());
+            }
+        }
+        if !trashed.is_empty() {
+            self.mark_dirty();
+        }
+        trashed
+    }
+
+    /// Clear the tombstone of every matched link that is in the trash.
+    pub fn restore_where(&mut self, selector: &LinkSelector) -> Vec<Link> {
+        let mut restored = Vec::new();
+        for l in &mut self.feed.links {
+            if l.deleted.is_some() && selector.matches(l) {
+                l.deleted = None;
+                restored.push(l.clone());
+            }
+        }
+        if !restored.is_empty() {
+            self.mark_dirty();
+        }
+        restored
+    }
+
+    /// Permanently remove trashed links whose tombstone is older than `older_than`.
+    pub fn purge_trash(&mut self, older_than: &DateTime) -> Vec<Link> {
+        let cutoff = instant_key(older_than);
+        self.remove_where(&LinkSelector::predicate(move |l| {
+            l.deleted.as_ref().is_some_and(|d| instant_key(d) < cutoff)
+        }))
+    }
+
+    /// Merge each group of duplicate links in memory (see [`crate::dedup`]).
+    /// The store is only marked dirty if duplicates were found.
+    pub fn merge_duplicates(&mut self, options: &DedupOptions) -> Vec<MergedGroup> {
+        let merged = dedup::merge_duplicates(&mut self.feed, options);
+        if !merged.is_empty() {
+            self.url_index = None;
+            self.mark_dirty();
+        }
+        merged
+    }
+
+    /// Every tag in the feed with its link count (see [`tags::tag_counts`]).
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        tags::tag_counts(&self.feed)
+    }
+
+    /// Make `alias` stand for `tag` (see [`tags::define_tag_alias`]). The
+    /// store is only marked dirty if the alias table changed.
+    ///
+    /// ## Errors
+    /// [`Error::Validation`] if the alias is empty or would resolve to itself.
+    pub fn define_tag_alias(&mut self, alias: &str, tag: &str) -> Result<()> {
+        let before = self.feed.tag_aliases.clone();
+        tags::define_tag_alias(&mut self.feed, alias, tag)?;
+        if self.feed.tag_aliases != before {
+            self.mark_dirty();
+        }
+        Ok(())
+    }
+
+    /// Remove `alias`, returning its target if it was defined.
+    pub fn remove_tag_alias(&mut self, alias: &str) -> Option<String> {
+        let removed = tags::remove_tag_alias(&mut self.feed, alias);
+        if removed.is_some() {
+            self.mark_dirty();
+        }
+        removed
+    }
+
+    /// The feed's tags as a hierarchy with counts per level (see
+    /// [`tags::tag_tree`]).
+    pub fn tag_tree(&self) -> Vec<TagNode> {
+        tags::tag_tree(&self.feed)
+    }
+
+    /// Rename tag `from` to `to` on every link (see [`tags::rename_tag`]).
+    /// Returns the ids of the changed links; `updated` timestamps are left
+    /// alone. The store is only marked dirty if a link changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Vec<String> {
+        let changed = tags::rename_tag(&mut self.feed, from, to);
+        if !changed.is_empty() {
+            self.mark_dirty();
+        }
+        changed
+    }
+
+    /// Replace the tags `from` by `into` on every link (see
+    /// [`tags::merge_tags`]). Like [`FeedStore::rename_tag`] otherwise.
+    pub fn merge_tags<S: AsRef<str>>(&mut self, from: &[S], into: &str) -> Vec<String> {
+        let changed = tags::merge_tags(&mut self.feed, from, into);
+        if !changed.is_empty() {
+            self.mark_dirty();
+        }
+        changed
+    }
+
+    /// Remove `tag` from every link (see [`tags::delete_tag`]). Like
+    /// [`FeedStore::rename_tag`] otherwise.
+    pub fn delete_tag(&mut self, tag: &str) -> Vec<String> {
+        let changed = tags::delete_tag(&mut self.feed, tag);
+        if !changed.is_empty() {
+            self.mark_dirty();
+        }
+        changed
+    }
+
+    /// Re-normalize the tags of every link with `policy` (see
+    /// [`tags::normalize_feed`]). Like [`FeedStore::rename_tag`] otherwise.
+    pub fn normalize_tags(&mut self, policy: &TagPolicy) -> Vec<String> {
+        let changed = tags::normalize_feed(&mut self.feed, policy);
+        if !changed.is_empty() {
+            self.mark_dirty();
+        }
+        changed
+    }
+
+    /// Fix the mechanically repairable integrity issues of the feed in memory
+    /// (see [`crate::check::repair`]). The store is only marked dirty if
+    /// something was fixed.
+    pub fn repair(&mut self) -> Vec<Issue> {
+        let fixed = check::repair(&mut self.feed);
+        if !fixed.is_empty() {
+            self.url_index = None;
+            self.mark_dirty();
+        }
+        fixed
+    }
+
+    /// Mark the store dirty, refreshing the lock if it is due.
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        if self.refreshed.elapsed() >= self.refresh_every {
+            // A lost lock is reported by the next `commit`.
+            let _ = self.lock.refresh();
+            self.refreshed = Instant::now();
+        }
+    }
+
+    /// Persist pending changes, if any.
+    ///
+    /// Bumps the feed's `revision`, writes it with
+    /// [`write_feed_if_unchanged_locked`] against the revision that was read,
+    /// and clears the dirty flag. Also refreshes the lock so long batches do
+    /// not look stale, and updates the feed's
+    /// [search index](mod@crate::search) if it has one.
+    ///
+    /// ## Errors
+    /// - [`Error::Conflict`] if the file was changed by someone else, which
+    ///   can only happen if the lock was broken as stale.
+    /// - [`Error::LockLost`] if the lock was broken, even if the file is
+    ///   unchanged.
+    /// - Any error from [`write_feed_if_unchanged_locked`].
+    ///
+    /// The store stays dirty on failure, so `commit` can be retried; after a
+    /// conflict, reopen the store and reapply the changes instead.
+    pub fn commit(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let expected = self.feed.revision;
+        self.feed =
+            write_feed_if_unchanged_locked(&self.lock, &self.path, self.feed.clone(), expected)?;
+        self.lock.refresh()?;
+        self.refreshed = Instant::now();
+        self.dirty = false;
+        crate::search::refresh_existing_index(&self.path, &self.feed);
+        #[cfg(feature = "logs")]
+        tracing::debug!(links = self.feed.links.len(), path = %self.path.display(), "feed written");
+        Ok(())
+    }
+
+    /// Consume the store, committing pending changes, and return the feed.
+    pub fn into_feed(mut self) -> Result<Feed> {
+        self.commit()?;
+        Ok(std::mem::take(&mut self.feed))
+    }
+}
+
+impl Drop for FeedStore {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            #[cfg(feature = "logs")]
+            tracing::warn!(path = %self.path.display(), dirty = self.dirty, "panicking; not committing feed on drop");
+            return;
+        }
+        if let Err(_err) = self.commit() {
+            #[cfg(feature = "logs")]
+            tracing::warn!(error = %_err, path = %self.path.display(), "failed to commit feed on drop");
+        }
+    }
+}
+
+/// Link positions by (canonical) URL, so that an upsert does not
+/// canonicalize every URL of the feed.
+///
+/// Positions are counted from the back of `feed.links`, which keeps them
+/// stable when a link is inserted at the front.
+#[derive(Debug)]
+struct UrlIndex {
+    canonical: Option<CanonicalOptions>,
+    by_key: HashMap<String, Vec<usize>>,
+}
+
+impl UrlIndex {
+    fn build(links: &[Link], canonical: &Option<CanonicalOptions>) -> Self {
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (rev, l) in links.iter().rev().enumerate() {
+            by_key
+                .entry(url_key(&l.url, canonical))
+                .or_default()
+                .push(rev);
+        }
+        UrlIndex {
+            canonical: canonical.clone(),
+            by_key,
+        }
+    }
+
+    /// Position of the first of `len` links with this key.
+    fn first(&self, key: &str, len: usize) -> Option<usize> {
+        let rev = self.by_key.get(key)?.iter().max()?;
+        Some(len - 1 - rev)
+    }
+
+    /// The link at `pos` of `len` links was removed.
+    fn remove(&mut self, key: &str, pos: usize, len: usize) {
+        let rev = len - 1 - pos;
+        if let Some(revs) = self.by_key.get_mut(key) {
+            revs.retain(|&r| r != rev);
+            if revs.is_empty() {
+                self.by_key.remove(key);
+            }
+        }
+        for r in self.by_key.values_mut().flatten() {
+            if *r > rev {
+                *r -= 1;
+            }
+        }
+    }
+
+    /// A link was inserted at `pos`; `len` counts it.
+    fn insert(&mut self, key: String, pos: usize, len: usize) {
+        let rev = len - 1 - pos;
+        for r in self.by_key.values_mut().flatten() {
+            if *r >= rev {
+                *r += 1;
+            }
+        }
+        self.by_key.entry(key).or_default().push(rev);
+    }
+}
+
+fn url_key(url: &str, canonical: &Option<CanonicalOptions>) -> String {
+    match canonical {
+        Some(canonical) => canonicalize_url_with(url, canonical),
+        None => url.to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_link_in_place(
+    feed: &mut Feed,
+    pos: usize,
+    title: String,
+    url: String,
+    summary: Option<Summary>,
+    tags: Vec<String>,
+    via: Option<Via>,
+    now: DateTime,
+    move_to_front: bool,
+) -> Link {
+    let item = &mut feed.links[pos];
+    item.title = title;
+    item.url = url;
+    item.summary = summary;
+    item.tags = tags;
+    item.via = via;
+    item.deleted = None;
+    touch(item, now);
+
+    let item = item.clone();
+    if move_to_front {
+        let moved = feed.links.remove(pos);
+        feed.links.insert(0, moved);
+    }
+    item
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_new_link_front(
+    feed: &mut Feed,
+    id: String,
+    title: String,
+    url: String,
+    summary: Option<Summary>,
+    tags: Vec<String>,
+    via: Option<Via>,
+    now: DateTime,
+) -> Link {
+    let link = Link {
+        summary,
+        tags,
+        via,
+        id,
+        title,
+        url,
+        datetime: Some(now),
+        created: Some(now),
+        updated: Some(now),
+        deleted: None,
+    };
+    feed.links.insert(0, link.clone());
+    link
+}
+
+/// Record a modification: `updated` becomes `now`, `created` and `datetime`
+/// are only filled in if they were missing.
+fn touch(link: &mut Link, now: DateTime) {
+    if link.created.is_none() {
+        link.created = link.datetime.or(Some(now));
+    }
+    if link.datetime.is_none() {
+        link.datetime = link.created;
+    }
+    link.updated = Some(now);
+}
+
+/// Ordering key for timestamps: seconds since the Unix epoch and nanos.
+///
+/// Honors [`DateTime::utc_offset`], so `10:00+02:00` and `08:00Z` are equal.
+/// Values without a known offset count as UTC. A leap second is second 59
+/// with nanos of a second or more (as in chrono), so it sorts between
+/// `23:59:59.999999999` and midnight.
+pub(crate) fn instant_key(dt: &DateTime) -> (i64, i32) {
+    let (seconds, nanos) = match dt.seconds {
+        60 => (59, 1_000_000_000 + dt.nanos),
+        s => (s, dt.nanos),
+    };
+    let days = days_from_civil(dt.year.into(), dt.month.into(), dt.day.into());
+    let secs = days * 86_400
+        + i64::from(dt.hours) * 3_600
+        + i64::from(dt.minutes) * 60
+        + i64::from(seconds)
+        - i64::from(dt.utc_offset.unwrap_or(0));
+    (secs, nanos)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date. Total: out-of-range
+/// months and days just roll over.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9).rem_euclid(12);
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddOptions, FeedStore, LinkPatch, LinkSelector, instant_key};
+    use crate::clock::{FixedClock, UtcClock};
+    use crate::error::Error;
+    use crate::fs::{FeedLock, LockOptions, lock_path, read_feed};
+    use crate::linkleaf_proto::{DateTime, Summary};
+    use crate::query::Query;
+    use crate::tags::TagPolicy;
+    use crate::validation::LinkRules;
+    use anyhow::Result;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    #[test]
+    fn batch_upserts_are_written_once_on_commit() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        assert!(!store.is_dirty());
+        for i in 0..100 {
+            store.upsert(
+                format!("Link {i}"),
+                format!("https://example.com/{i}"),
+                None,
+                Vec::<String>::new(),
+                None,
+                None,
+            )?;
+        }
+        assert!(store.is_dirty());
+        assert!(!file.exists(), "nothing is written before commit");
+
+        store.commit()?;
+        assert!(!store.is_dirty());
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.links.len(), 100);
+        assert_eq!(feed.revision, 1, "one commit -> one revision");
+        assert_eq!(feed.links[0].title, "Link 99", "newest first");
+        Ok(())
+    }
+
+    #[test]
+    fn url_index_follows_upserts_and_removals() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FeedStore::open(dir.path().join("feed.pb"))?;
+        let keep_place = AddOptions {
+            move_to_front: false,
+            ..Default::default()
+        };
+        let none: [&str; 0] = [];
+
+        let a = store.upsert("A", "https://a.example/", None, none, None, None)?;
+        let b = store.upsert("B", "https://b.example/", None, none, None, None)?;
+        // A second link for a's URL, added by id.
+        let a2 = store.upsert(
+            "A2",
+            "https://a.example",
+            None,
+            none,
+            None,
+            Some(Uuid::new_v4()),
+        )?;
+        store.upsert("C", "https://c.example/", None, none, None, None)?;
+
+        let again = store.upsert("A again", "https://A.example/#x", None, none, None, None)?;
+        assert_eq!(again.id, a2.id, "first match in stored order");
+        let again = store.upsert_with(
+            "B again",
+            "https://b.example",
+            None,
+            none,
+            None,
+            None,
+            &keep_place,
+        )?;
+        assert_eq!(again.id, b.id);
+
+        store.remove(&a2.id);
+        let again = store.upsert(
+            "A",
+            "https://a.example/?utm_source=x",
+            None,
+            none,
+            None,
+            None,
+        )?;
+        assert_eq!(again.id, a.id, "falls back to the remaining duplicate");
+
+        // Changing a URL by id re-keys the link.
+        store.upsert(
+            "B",
+            "https://d.example/",
+            None,
+            none,
+            None,
+            Some(b.id.parse()?),
+        )?;
+        let d = store.upsert("D", "https://d.example", None, none, None, None)?;
+        assert_eq!(d.id, b.id);
+        let new_b = store.upsert("B", "https://b.example/", None, none, None, None)?;
+        assert_ne!(new_b.id, b.id);
+
+        let titles: Vec<_> = store.iter().map(|l| l.title.as_str()).collect();
+        assert_eq!(titles, ["B", "D", "A", "C"]);
+        Ok(())
+    }
+
+    #[test]
+    fn repair_marks_store_dirty_only_when_something_was_fixed() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let as_given = AddOptions {
+            tags: TagPolicy::preserve(),
+            ..Default::default()
+        };
+        store.upsert_with("A", "https://a/", None, ["x", "X"], None, None, &as_given)?;
+        store.commit()?;
+
+        assert_eq!(store.repair().len(), 1, "duplicate tag");
+        assert!(store.is_dirty());
+        store.commit()?;
+        assert!(store.repair().is_empty());
+        assert!(!store.is_dirty());
+        assert_eq!(store.feed().links[0].tags, vec!["x"]);
+        Ok(())
+    }
+
+    #[test]
+    fn get_iter_query_and_remove_work_in_memory() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let a = store.upsert("A", "https://a/", None, ["rust"], None, None)?;
+        let b = store.upsert("B", "https://b/", None, ["Go"], None, None)?;
+
+        assert_eq!(store.get(&a.id).map(|l| l.title.as_str()), Some("A"));
+        assert_eq!(store.iter().count(), 2);
+
+        let go = store.query(&Query::any_tag(["go"]));
+        assert_eq!(go.len(), 1);
+        assert_eq!(go[0].id, b.id);
+
+        assert_eq!(store.remove(&a.id).map(|l| l.id), Some(a.id.clone()));
+        assert!(store.remove(&a.id).is_none());
+        assert!(store.get(&a.id).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn remove_where_by_url_and_predicate() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        store.upsert("A", "https://a/", None, ["old"], None, None)?;
+        store.upsert("B", "https://b/", None, ["old"], None, None)?;
+        store.upsert("C", "https://c/", None, ["new"], None, None)?;
+        store.commit()?;
+
+        let removed = store.remove_where(&LinkSelector::url("https://b/"));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].title, "B");
+
+        let removed =
+            store.remove_where(&LinkSelector::predicate(|l| l.tags.contains(&"old".into())));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].title, "A");
+        assert_eq!(store.iter().count(), 1);
+
+        store.commit()?;
+        assert!(store.remove_where(&LinkSelector::id("nope")).is_empty());
+        assert!(!store.is_dirty(), "no match leaves the store clean");
+        Ok(())
+    }
+
+    #[test]
+    fn patch_only_touches_named_fields() -> Result<()> {
+        let mut link = crate::linkleaf_proto::Link {
+            title: "Old".into(),
+            url: "https://old/".into(),
+            summary: Some(Summary::new("keep me")),
+            tags: vec!["rust".into(), "Draft".into()],
+            ..Default::default()
+        };
+
+        LinkPatch::new()
+            .title("New")
+            .remove_tag("draft")
+            .add_tag("async")
+            .add_tag("RUST")
+            .apply_to(&mut link)?;
+
+        assert_eq!(link.title, "New");
+        assert_eq!(link.url, "https://old/");
+        assert_eq!(link.summary, Some(Summary::new("keep me")));
+        assert_eq!(link.tags, vec!["rust", "async"]);
+
+        LinkPatch::new().summary(None).apply_to(&mut link)?;
+        assert_eq!(link.summary, None);
+
+        let err = LinkPatch::new().url(" ").apply_to(&mut link).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)), "{err}");
+        assert_eq!(link.url, "https://old/", "left unchanged");
+        let strict = LinkRules::default();
+        assert!(
+            LinkPatch::new()
+                .url("old")
+                .validation(strict)
+                .apply_to(&mut link)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn update_moves_to_front_unless_position_preserved() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let a = store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        store.upsert("B", "https://b/", None, ["y"], None, None)?;
+
+        let stamped_before = store.get(&a.id).unwrap().datetime;
+        let kept = store.update(&a.id, &LinkPatch::new().title("A2").preserve_position(true))?;
+        assert_eq!(kept.title, "A2");
+        assert_eq!(kept.datetime, stamped_before);
+        assert_eq!(kept.created, a.created);
+        assert!(instant_key(&kept.updated.unwrap()) >= instant_key(&a.updated.unwrap()));
+        assert_eq!(store.iter().nth(1).unwrap().id, a.id, "still second");
+
+        store.update(&a.id, &LinkPatch::new().add_tag("z"))?;
+        let front = store.iter().next().unwrap();
+        assert_eq!(front.id, a.id);
+        assert_eq!(front.tags, vec!["x", "z"]);
+
+        assert!(matches!(
+            store.update("missing", &LinkPatch::new()),
+            Err(Error::LinkNotFound { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn check_accepts_updates_that_preserve_position() -> Result<()> {
+        let dir = tempdir()?;
+        let at = |s: &str| -> Result<AddOptions> {
+            Ok(AddOptions {
+                clock: Some(Arc::new(FixedClock(DateTime::parse(s)?))),
+                ..Default::default()
+            })
+        };
+        let later = FixedClock(DateTime::parse("2025-03-03T00:00:00Z")?);
+        let mut store = FeedStore::open(dir.path().join("feed.pb"))?.with_clock(later);
+        let none: [&str; 0] = [];
+        let a = store.upsert_with(
+            "A",
+            "https://a/",
+            None,
+            none,
+            None,
+            None,
+            &at("2025-03-01T00:00:00Z")?,
+        )?;
+        store.upsert_with(
+            "B",
+            "https://b/",
+            None,
+            none,
+            None,
+            None,
+            &at("2025-03-02T00:00:00Z")?,
+        )?;
+
+        store.update(&a.id, &LinkPatch::new().title("A2").preserve_position(true))?;
+        assert_eq!(store.iter().nth(1).unwrap().id, a.id);
+        assert_eq!(crate::check::check(store.feed()), []);
+        Ok(())
+    }
+
+    #[test]
+    fn check_accepts_links_moved_to_the_front() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FeedStore::open(dir.path().join("feed.pb"))?;
+        let none: [&str; 0] = [];
+        for (day, title, url) in [
+            (1, "A", "https://a/"),
+            (2, "B", "https://b/"),
+            (3, "A", "https://a/"),
+        ] {
+            let at = DateTime::parse(&format!("2025-03-0{day}T00:00:00Z"))?;
+            let options = AddOptions {
+                clock: Some(Arc::new(FixedClock(at))),
+                ..Default::default()
+            };
+            store.upsert_with(title, url, None, none, None, None, &options)?;
+        }
+        let order: Vec<_> = store.iter().map(|l| l.title.clone()).collect();
+        assert_eq!(order, ["A", "B"], "re-added A moved to the front");
+
+        assert_eq!(crate::check::check(store.feed()), []);
+        assert_eq!(store.repair(), []);
+        let after: Vec<_> = store.iter().map(|l| l.title.clone()).collect();
+        assert_eq!(after, order);
+        Ok(())
+    }
+
+    #[test]
+    fn upsert_keeps_created_and_optionally_position() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let a = store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        assert!(a.created.is_some());
+        assert_eq!(a.created, a.updated);
+        assert_eq!(a.created, a.datetime);
+        store.upsert("B", "https://b/", None, ["y"], None, None)?;
+
+        let stay = AddOptions {
+            move_to_front: false,
+            ..Default::default()
+        };
+        let a2 = store.upsert_with(
+            "A (typo fixed)",
+            "https://a/",
+            None,
+            ["x"],
+            None,
+            None,
+            &stay,
+        )?;
+        assert_eq!(a2.id, a.id);
+        assert_eq!(a2.created, a.created, "creation time survives updates");
+        assert_eq!(a2.datetime, a.datetime);
+        assert_eq!(store.iter().nth(1).unwrap().id, a.id, "not moved");
+
+        store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        assert_eq!(
+            store.iter().next().unwrap().id,
+            a.id,
+            "default moves to front"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn update_backfills_created_from_legacy_datetime() {
+        let legacy = DateTime {
+            year: 2023,
+            month: 5,
+            day: 1,
+            ..Default::default()
+        };
+        let now = DateTime {
+            year: 2025,
+            month: 1,
+            day: 1,
+            ..Default::default()
+        };
+        let mut link = crate::linkleaf_proto::Link {
+            datetime: Some(legacy),
+            ..Default::default()
+        };
+        super::touch(&mut link, now);
+        assert_eq!(link.created, Some(legacy));
+        assert_eq!(link.datetime, Some(legacy));
+        assert_eq!(link.updated, Some(now));
+    }
+
+    #[test]
+    fn timestamps_record_and_compare_by_utc_offset() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FeedStore::open(dir.path().join("feed.pb"))?;
+        let link = store.upsert("A", "https://a/", None, None, None, None)?;
+        let created = link.created.unwrap();
+        assert!(created.utc_offset.is_some(), "offset recorded on write");
+
+        let at = |hours, offset| {
+            DateTime {
+                year: 2025,
+                month: 3,
+                day: 1,
+                hours,
+                ..Default::default()
+            }
+            .with_utc_offset(offset)
+        };
+        assert_eq!(instant_key(&at(10, 2 * 3600)), instant_key(&at(8, 0)));
+        assert!(instant_key(&at(10, 2 * 3600)) < instant_key(&at(9, 0)));
+        assert_eq!(instant_key(&at(0, 0)).0, 1_740_787_200);
+
+        let leap = DateTime::parse("2016-12-31T23:59:60.5Z")?;
+        assert!(DateTime::parse("2016-12-31T23:59:59.999Z")? < leap);
+        assert!(leap < DateTime::parse("2017-01-01T00:00:00Z")?);
+        Ok(())
+    }
+
+    #[test]
+    fn clocks_make_timestamps_deterministic() -> Result<()> {
+        let dir = tempdir()?;
+        let t1 = DateTime::parse("2025-03-14T09:00:00+01:00")?;
+        let t2 = DateTime::parse("2025-03-15T09:00:00Z")?;
+        let mut store = FeedStore::open(dir.path().join("feed.pb"))?.with_clock(FixedClock(t1));
+
+        let a = store.upsert("A", "https://a/", None, None, None, None)?;
+        assert_eq!(
+            (a.created, a.updated, a.datetime),
+            (Some(t1), Some(t1), Some(t1))
+        );
+
+        // A per-call clock wins over the store's.
+        let options = AddOptions {
+            clock: Some(Arc::new(FixedClock(t2))),
+            ..Default::default()
+        };
+        let a = store.upsert_with("A2", "https://a/", None, None, None, None, &options)?;
+        assert_eq!((a.created, a.updated), (Some(t1), Some(t2)));
+        assert_eq!(options, AddOptions::default(), "the clock is not compared");
+
+        let t3 = DateTime::parse("2025-03-16T09:00:00Z")?;
+        let a = store.update(&a.id, &LinkPatch::new().title("A3").clock(FixedClock(t3)))?;
+        assert_eq!((a.created, a.updated), (Some(t1), Some(t3)));
+
+        let trashed = store.trash_where(&LinkSelector::id(a.id.clone()));
+        assert_eq!(trashed[0].deleted, Some(t1));
+
+        let mut store = store.with_clock(UtcClock);
+        let b = store.upsert("B", "https://b/", None, None, None, None)?;
+        assert_eq!(b.created.and_then(|c| c.utc_offset), Some(0));
+        Ok(())
+    }
+
+    #[test]
+    fn trash_restore_and_purge() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let a = store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        let b = store.upsert("B", "https://b/", None, ["x"], None, None)?;
+
+        let trashed = store.trash_where(&LinkSelector::predicate(|_| true));
+        assert_eq!(trashed.len(), 2);
+        assert!(store.query(&Query::any_tag(["x"])).is_empty());
+        assert!(
+            store
+                .trash_where(&LinkSelector::id(a.id.clone()))
+                .is_empty(),
+            "already trashed"
+        );
+
+        let restored = store.restore_where(&LinkSelector::id(a.id.clone()));
+        assert_eq!(restored.len(), 1);
+        assert!(store.get(&a.id).unwrap().deleted.is_none());
+
+        // Nothing was trashed before the epoch.
+        let epoch = DateTime {
+            year: 1970,
+            month: 1,
+            day: 1,
+            ..Default::default()
+        };
+        assert!(store.purge_trash(&epoch).is_empty());
+
+        let far_future = DateTime {
+            year: 9999,
+            month: 12,
+            day: 31,
+            ..Default::default()
+        };
+        let purged = store.purge_trash(&far_future);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].id, b.id);
+        assert_eq!(store.iter().count(), 1, "restored link is kept");
+        Ok(())
+    }
+
+    #[test]
+    fn upsert_restores_trashed_link() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        let a = store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        store.trash_where(&LinkSelector::id(a.id.clone()));
+
+        let again = store.upsert("A", "https://a/", None, ["x"], None, None)?;
+        assert_eq!(again.id, a.id);
+        assert!(again.deleted.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn drop_commits_pending_changes_and_releases_lock() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        {
+            let mut store = FeedStore::open(&file)?;
+            store.upsert("A", "https://a/", None, ["x"], None, None)?;
+
+            // The lock is held for the lifetime of the store.
+            let opts = LockOptions {
+                timeout: Duration::from_millis(20),
+                ..Default::default()
+            };
+            assert!(matches!(
+                FeedLock::acquire_with(&file, &opts),
+                Err(Error::Lock { .. })
+            ));
+        }
+
+        assert_eq!(read_feed(&file)?.links.len(), 1);
+        let _lock = FeedLock::acquire(&file)?;
+        Ok(())
+    }
+
+    #[test]
+    fn commit_refuses_to_clobber_after_the_lock_was_broken() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let mut store = FeedStore::open(&file)?;
+        store.upsert("Ours", "https://ours/", None, None, None, None)?;
+
+        // Left open without a commit until the lock looks stale; another
+        // process breaks it and writes.
+        let f = std::fs::OpenOptions::new()
+            .write(true)
+            .open(lock_path(&file))?;
+        f.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        drop(f);
+        crate::add(&file, "Theirs", "https://theirs/", None, None, None, None)?;
+
+        let err = store.commit().unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+        drop(store);
+        let feed = read_feed(&file)?;
+        assert_eq!(feed.links.len(), 1);
+        assert_eq!(feed.links[0].title, "Theirs", "their write survives");
+        Ok(())
+    }
+
+    #[test]
+    fn mutations_keep_the_lock_fresh() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let opts = LockOptions {
+            stale_after: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut store = FeedStore::open_with(&file, &opts)?;
+        let f = std::fs::OpenOptions::new()
+            .write(true)
+            .open(lock_path(&file))?;
+        f.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        drop(f);
+
+        store.upsert("A", "https://a/", None, None, None, None)?;
+        let modified = std::fs::metadata(lock_path(&file))?.modified()?;
+        assert!(modified.elapsed()? < Duration::from_secs(60));
+        Ok(())
+    }
+
+    #[test]
+    fn panicking_drop_does_not_commit() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+        let path = file.clone();
+        let result = std::thread::spawn(move || {
+            let mut store = FeedStore::open(&path).unwrap();
+            store
+                .upsert("Half", "https://half/", None, None, None, None)
+                .unwrap();
+            panic!("batch failed halfway");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(!file.exists(), "half-applied batch not persisted");
+        assert!(!lock_path(&file).exists(), "lock still released");
+        Ok(())
+    }
+
+    #[test]
+    fn commit_without_changes_does_not_write() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("feed.pb");
+
+        let mut store = FeedStore::open(&file)?;
+        store.commit()?;
+        drop(store);
+        assert!(!file.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+//! Tag normalization and feed-wide tag management.
+//!
+//! [`TagPolicy`] decides how tags are stored. [`add`](crate::add) and
+//! [`LinkPatch`](crate::LinkPatch) apply it to the tags they write; the
+//! functions here apply it (and renames, merges and deletions) to a whole
+//! [`Feed`].
+//!
+//! Tags are compared *folded*: trimmed, inner whitespace collapsed and
+//! lowercased (Unicode-aware), so `"Rust"`, `"rust"` and `"RUST "` are the
+//! same tag.
+//!
+//! Tags can be hierarchical paths separated by [`SEPARATOR`] (`lang/rust`).
+//! A tag filter matches the tag itself and everything below it, so `lang`
+//! matches `lang/rust` but not `language` (see [`tag_matches`]), and
+//! [`tag_tree`] aggregates counts per level.
+//!
+//! A feed can also define tag aliases (`k8s` → `kubernetes`, stored in
+//! `Feed.tag_aliases`). Counts, normalization and the feed-wide operations
+//! here treat an alias as its target; see [`define_tag_alias`].
+
+use crate::error::{Error, Result};
+use crate::linkleaf_proto::{Feed, Link};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Separator between the levels of a hierarchical tag.
+pub const SEPARATOR: char = '/';
+
+/// How tags are normalized before they are stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagPolicy {
+    /// Lowercase tags (Unicode-aware). Defaults to `true`.
+    pub lowercase: bool,
+    /// Trim tags (and each level of a hierarchical tag) and collapse inner
+    /// runs of whitespace to one space. Defaults to `true`.
+    pub collapse_whitespace: bool,
+    /// Turn tags into slugs: lowercase, with every run of characters that are
+    /// not letters or digits replaced by `-` (`"Machine Learning"` →
+    /// `"machine-learning"`). [`SEPARATOR`]s are kept. Defaults to `false`.
+    pub slug: bool,
+    /// Drop tags that fold to the same value as an earlier tag. Defaults to
+    /// `true`.
+    pub dedupe: bool,
+    /// Replace aliases by their target when the feed's aliases are supplied
+    /// (see [`TagPolicy::normalize_with_aliases`]). Defaults to `true`.
+    pub resolve_aliases: bool,
+}
+
+impl Default for TagPolicy {
+    fn default() -> Self {
+        TagPolicy {
+            lowercase: true,
+            collapse_whitespace: true,
+            slug: false,
+            dedupe: true,
+            resolve_aliases: true,
+        }
+    }
+}
+
+impl TagPolicy {
+    /// Store tags as given, only dropping empty ones.
+    pub fn preserve() -> Self {
+        TagPolicy {
+            lowercase: false,
+            collapse_whitespace: false,
+            slug: false,
+            dedupe: false,
+            resolve_aliases: false,
+        }
+    }
+
+    /// Normalize one tag. Returns `None` if nothing is left of it.
+    pub fn normalize(&self, tag: &str) -> Option<String> {
+        let mut out = if self.slug {
+            map_levels(tag, slugify)
+        } else if self.collapse_whitespace {
+            map_levels(tag, collapse_whitespace)
+        } else {
+            tag.to_string()
+        };
+        if self.lowercase && !self.slug {
+            out = out.to_lowercase();
+        }
+        (!out.trim().is_empty()).then_some(out)
+    }
+
+    /// Normalize a list of tags, dropping empty ones and (if enabled)
+    /// duplicates. Order is preserved.
+    pub fn normalize_all<S: AsRef<str>, I: IntoIterator<Item = S>>(&self, tags: I) -> Vec<String> {
+        self.normalize_with_aliases(tags, &BTreeMap::new())
+    }
+
+    /// Like [`TagPolicy::normalize_all`], also replacing aliases from
+    /// `aliases` (a feed's `tag_aliases`) by their target if
+    /// [`TagPolicy::resolve_aliases`] is set.
+    pub fn normalize_with_aliases<S: AsRef<str>, I: IntoIterator<Item = S>>(
+        &self,
+        tags: I,
+        aliases: &BTreeMap<String, String>,
+    ) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.as_ref();
+            let tag = if self.resolve_aliases {
+                resolve_tag(aliases, tag)
+            } else {
+                tag.to_string()
+            };
+            if let Some(tag) = self.normalize(&tag) {
+                if self.dedupe && out.iter().any(|t| same_tag(t, &tag)) {
+                    continue;
+                }
+                out.push(tag);
+            }
+        }
+        out
+    }
+}
+
+/// Comparison form of a tag: each level trimmed, whitespace collapsed,
+/// lowercased.
+pub fn fold_tag(tag: &str) -> String {
+    map_levels(tag, collapse_whitespace).to_lowercase()
+}
+
+/// `tag` with a defined alias replaced by its target. The longest aliased
+/// prefix of a hierarchical tag is replaced too: with `k8s` → `kubernetes`,
+/// `k8s/helm` resolves to `kubernetes/helm`. Tags without an alias are
+/// returned unchanged.
+pub fn resolve_tag(aliases: &BTreeMap<String, String>, tag: &str) -> String {
+    if !aliases.is_empty() {
+        let levels: Vec<&str> = tag.split(SEPARATOR).collect();
+        for n in (1..=levels.len()).rev() {
+            let key = fold_tag(&levels[..n].join(&SEPARATOR.to_string()));
+            if let Some(target) = aliases.get(&key) {
+                let mut out = target.clone();
+                for level in &levels[n..] {
+                    out.push(SEPARATOR);
+                    out.push_str(level.trim());
+                }
+                return out;
+            }
+        }
+    }
+    tag.to_string()
+}
+
+/// Make `alias` stand for `tag` in `feed`.
+///
+/// ## Behavior
+/// - The alias is stored folded. `tag` is resolved through the existing
+///   aliases first, so targets are never aliases themselves.
+/// - Aliases pointing at `alias` (or below it) are re-pointed at the new
+///   target, keeping alias lookups single-step.
+/// - Link tags are left as they are; use [`normalize_feed`] to rewrite them.
+///
+/// ## Errors
+/// [`Error::Validation`] if `alias` or `tag` is empty, or if `tag` resolves
+/// to `alias` itself or a tag below it.
+pub fn define_tag_alias(feed: &mut Feed, alias: &str, tag: &str) -> Result<()> {
+    let key = fold_tag(alias);
+    if key.is_empty() || fold_tag(tag).is_empty() {
+        return Err(Error::Validation(
+            "tag alias and target must not be empty".into(),
+        ));
+    }
+    let target = resolve_tag(&feed.tag_aliases, tag.trim());
+    if tag_matches(&target, &key) {
+        return Err(Error::Validation(format!(
+            "tag alias {alias:?} would resolve to itself ({target:?})"
+        )));
+    }
+    feed.tag_aliases.insert(key.clone(), target);
+    let aliases = feed.tag_aliases.clone();
+    for value in feed.tag_aliases.values_mut() {
+        if tag_matches(value, &key) {
+            *value = resolve_tag(&aliases, value);
+        }
+    }
+    Ok(())
+}
+
+/// Remove `alias` from `feed`, returning its target if it was defined.
+pub fn remove_tag_alias(feed: &mut Feed, alias: &str) -> Option<String> {
+    feed.tag_aliases.remove(&fold_tag(alias))
+}
+
+/// `true` if `a` and `b` are the same tag once folded.
+pub fn same_tag(a: &str, b: &str) -> bool {
+    a == b || fold_tag(a) == fold_tag(b)
+}
+
+/// `true` if `tag` is `filter` or lies below it in the tag hierarchy
+/// (`lang/rust` matches `lang` and `lang/rust`, not `lang/r` or `language`).
+/// Both are compared folded.
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let tag = fold_tag(tag);
+    let filter = fold_tag(filter);
+    tag.strip_prefix(filter.as_str())
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(SEPARATOR))
+}
+
+/// One level of the [`tag_tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagNode {
+    /// This level's name (`rust` for `lang/rust`).
+    pub name: String,
+    /// The full, folded tag path (`lang/rust`).
+    pub path: String,
+    /// Links tagged with this path or anything below it, each counted once.
+    pub count: usize,
+    /// The levels below, sorted by name.
+    pub children: Vec<TagNode>,
+}
+
+/// The tags of `feed` as a tree: `lang/rust` and `lang/go` become a `lang`
+/// node with two children. Counts aggregate per level; aliases count as their
+/// target and trashed links are skipped. Roots and children are sorted by
+/// name.
+pub fn tag_tree(feed: &Feed) -> Vec<TagNode> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for link in feed.links.iter().filter(|l| l.deleted.is_none()) {
+        let mut paths: BTreeSet<String> = BTreeSet::new();
+        for tag in &link.tags {
+            let mut path = String::new();
+            for level in levels(&fold_tag(&resolve_tag(&feed.tag_aliases, tag))) {
+                if !path.is_empty() {
+                    path.push(SEPARATOR);
+                }
+                path.push_str(level);
+                paths.insert(path.clone());
+            }
+        }
+        for path in paths {
+            *counts.entry(path).or_default() += 1;
+        }
+    }
+
+    let mut roots: Vec<TagNode> = Vec::new();
+    for (path, count) in counts {
+        // Every prefix of `path` was counted too, and sorts before it.
+        let mut nodes = &mut roots;
+        let mut levels = levels(&path).peekable();
+        while let Some(level) = levels.next() {
+            let pos = match nodes.iter().position(|n| n.name == level) {
+                Some(pos) => pos,
+                None => {
+                    nodes.push(TagNode {
+                        name: level.to_string(),
+                        path: path.clone(),
+                        count,
+                        children: Vec::new(),
+                    });
+                    nodes.len() - 1
+                }
+            };
+            if levels.peek().is_none() {
+                break;
+            }
+            nodes = &mut nodes[pos].children;
+        }
+    }
+    sort_nodes(&mut roots);
+    roots
+}
+
+/// Every tag of `feed` with the number of links carrying it, most used first
+/// (ties by name). Tags are counted folded, aliases as their target; trashed
+/// links are skipped.
+pub fn tag_counts(feed: &Feed) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for link in feed.links.iter().filter(|l| l.deleted.is_none()) {
+        let mut seen: Vec<String> = Vec::new();
+        for tag in &link.tags {
+            let folded = fold_tag(&resolve_tag(&feed.tag_aliases, tag));
+            if !folded.is_empty() && !seen.contains(&folded) {
+                *counts.entry(folded.clone()).or_default() += 1;
+                seen.push(folded);
+            }
+        }
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Re-normalize the tags of every link according to `policy`, resolving the
+/// feed's aliases (see [`TagPolicy::normalize_with_aliases`]).
+///
+/// ## Returns
+/// The ids of the links whose tags changed.
+pub fn normalize_feed(feed: &mut Feed, policy: &TagPolicy) -> Vec<String> {
+    let aliases = &feed.tag_aliases;
+    edit_tags(&mut feed.links, |tags| {
+        policy.normalize_with_aliases(tags.iter(), aliases)
+    })
+}
+
+/// Rename tag `from` to `to` on every link (matching folded, aliases as
+/// their target). `to` is stored normalized with [`TagPolicy::default`] and
+/// alias-resolved, like a tag given to [`add`](crate::add). A link that
+/// already has `to` just loses `from`.
+///
+/// ## Returns
+/// The ids of the links that changed.
+pub fn rename_tag(feed: &mut Feed, from: &str, to: &str) -> Vec<String> {
+    merge_tags(feed, &[from], to)
+}
+
+/// Replace every tag in `from` by `into` on every link (matching folded,
+/// aliases as their target), keeping the position of the first replaced tag.
+/// `into` is normalized and alias-resolved as in [`rename_tag`].
+///
+/// ## Returns
+/// The ids of the links that changed.
+pub fn merge_tags<S: AsRef<str>>(feed: &mut Feed, from: &[S], into: &str) -> Vec<String> {
+    let aliases = &feed.tag_aliases;
+    let from: Vec<String> = from.iter().map(|t| resolved(aliases, t.as_ref())).collect();
+    let into = TagPolicy::default()
+        .normalize_with_aliases([into], aliases)
+        .pop()
+        .unwrap_or_default();
+    edit_tags(&mut feed.links, |tags| {
+        let mut out: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = if from.contains(&resolved(aliases, tag)) {
+                into.as_str()
+            } else {
+                tag.as_str()
+            };
+            if !tag.is_empty() && !out.iter().any(|t| same_tag(t, tag)) {
+                out.push(tag.to_string());
+            }
+        }
+        out
+    })
+}
+
+/// Remove tag `tag` (matching folded, aliases as their target) from every
+/// link.
+///
+/// ## Returns
+/// The ids of the links that changed.
+pub fn delete_tag(feed: &mut Feed, tag: &str) -> Vec<String> {
+    let aliases = &feed.tag_aliases;
+    let tag = resolved(aliases, tag);
+    edit_tags(&mut feed.links, |tags| {
+        tags.iter()
+            .filter(|t| resolved(aliases, t) != tag)
+            .cloned()
+            .collect()
+    })
+}
+
+/// Folded form of `tag` after alias resolution.
+fn resolved(aliases: &BTreeMap<String, String>, tag: &str) -> String {
+    fold_tag(&resolve_tag(aliases, tag))
+}
+
+/// Apply `f` to the tags of every link; return the ids of changed links.
+fn edit_tags<F: FnMut(&[String]) -> Vec<String>>(links: &mut [Link], mut f: F) -> Vec<String> {
+    links
+        .iter_mut()
+        .filter_map(|link: &mut Link| {
+            let tags = f(&link.tags);
+            (tags != link.tags).then(|| {
+                link.tags = tags;
+                link.id.clone()
+            })
+        })
+        .collect()
+}
+
+/// The non-empty levels of a hierarchical tag.
+fn levels(tag: &str) -> impl Iterator<Item = &str> {
+    tag.split(SEPARATOR).filter(|l| !l.is_empty())
+}
+
+/// Apply `f` to every level of `tag`, keeping the separators.
+fn map_levels(tag: &str, f: fn(&str) -> String) -> String {
+    tag.split(SEPARATOR)
+        .map(f)
+        .collect::<Vec<_>>()
+        .join(&SEPARATOR.to_string())
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn sort_nodes(nodes: &mut [TagNode]) {
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    for node in nodes {
+        sort_nodes(&mut node.children);
+    }
+}
+
+fn slugify(tag: &str) -> String {
+    let mut out = String::with_capacity(tag.len());
+    for c in tag.chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    while out.ends_with('-') {
+        out.pop();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TagNode, TagPolicy, define_tag_alias, delete_tag, merge_tags, normalize_feed,
+        remove_tag_alias, rename_tag, resolve_tag, tag_counts, tag_matches, tag_tree,
+    };
+    use crate::error::Error;
+    use crate::linkleaf_proto::{Feed, Link};
+
+    fn feed(tag_sets: &[&[&str]]) -> Feed {
+        Feed {
+            version: 1,
+            links: tag_sets
+                .iter()
+                .enumerate()
+                .map(|(i, tags)| Link {
+                    id: i.to_string(),
+                    tags: tags.iter().map(|t| t.to_string()).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_policy_folds_case_whitespace_and_duplicates() {
+        let p = TagPolicy::default();
+        assert_eq!(
+            p.normalize_all(["Rust", "rust", "RUST ", " Machine   Learning", "", "ÉTÉ"]),
+            vec!["rust", "machine learning", "été"]
+        );
+
+        let slug = TagPolicy {
+            slug: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            slug.normalize_all(["Machine Learning!", "C++", "--"]),
+            vec!["machine-learning", "c"]
+        );
+
+        assert_eq!(
+            TagPolicy::preserve().normalize_all(["Rust", "rust", " "]),
+            vec!["Rust", "rust"]
+        );
+    }
+
+    #[test]
+    fn counts_fold_tags_and_skip_trash() {
+        let mut f = feed(&[&["Rust", "async"], &["rust"], &["go", "GO"], &["go"]]);
+        f.links[3].deleted = Some(Default::default());
+        assert_eq!(
+            tag_counts(&f),
+            vec![
+                ("rust".to_string(), 2),
+                ("async".to_string(), 1),
+                ("go".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_merge_delete_and_normalize() {
+        let mut f = feed(&[&["k8s", "Ops"], &["kube", "kubernetes"], &["Rust"]]);
+
+        assert_eq!(rename_tag(&mut f, "OPS", "devops"), vec!["0"]);
+        assert_eq!(f.links[0].tags, vec!["k8s", "devops"]);
+
+        assert_eq!(
+            merge_tags(&mut f, &["k8s", "kube"], "kubernetes"),
+            vec!["0", "1"]
+        );
+        assert_eq!(f.links[0].tags, vec!["kubernetes", "devops"]);
+        assert_eq!(f.links[1].tags, vec!["kubernetes"]);
+
+        assert_eq!(delete_tag(&mut f, "DevOps"), vec!["0"]);
+        assert_eq!(f.links[0].tags, vec!["kubernetes"]);
+
+        assert_eq!(normalize_feed(&mut f, &TagPolicy::default()), vec!["2"]);
+        assert_eq!(f.links[2].tags, vec!["rust"]);
+        assert!(delete_tag(&mut f, "absent").is_empty());
+    }
+
+    #[test]
+    fn hierarchical_tags_match_by_level() {
+        assert!(tag_matches("lang/rust", "lang"));
+        assert!(tag_matches("Lang / Rust", "lang/rust"));
+        assert!(tag_matches("lang", "LANG"));
+        assert!(!tag_matches("lang/rust", "lang/r"));
+        assert!(!tag_matches("language", "lang"));
+        assert!(!tag_matches("lang", "lang/rust"));
+
+        let slug = TagPolicy {
+            slug: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            slug.normalize_all(["Lang / Machine Learning"]),
+            vec!["lang/machine-learning"]
+        );
+    }
+
+    #[test]
+    fn tag_tree_aggregates_counts_per_level() {
+        let node = |name: &str, path: &str, count, children| TagNode {
+            name: name.into(),
+            path: path.into(),
+            count,
+            children,
+        };
+        let f = feed(&[
+            &["lang/rust", "lang/rust/async"],
+            &["Lang/Go"],
+            &["lang", "web"],
+            &["lang-x"],
+        ]);
+        assert_eq!(
+            tag_tree(&f),
+            vec![
+                node(
+                    "lang",
+                    "lang",
+                    3,
+                    vec![
+                        node("go", "lang/go", 1, vec![]),
+                        node(
+                            "rust",
+                            "lang/rust",
+                            1,
+                            vec![node("async", "lang/rust/async", 1, vec![])]
+                        ),
+                    ]
+                ),
+                node("lang-x", "lang-x", 1, vec![]),
+                node("web", "web", 1, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn aliases_resolve_in_counts_ops_and_normalization() {
+        let mut f = feed(&[&["kubernetes"], &["k8s", "K8S/helm"], &["kube"]]);
+        define_tag_alias(&mut f, "kube", "k8s").unwrap();
+        // Re-pointed: `kube` -> `k8s` becomes `kube` -> `kubernetes`.
+        define_tag_alias(&mut f, "K8s", "kubernetes").unwrap();
+        assert_eq!(f.tag_aliases["kube"], "kubernetes");
+        assert_eq!(f.tag_aliases["k8s"], "kubernetes");
+        assert_eq!(resolve_tag(&f.tag_aliases, "k8s / Helm"), "kubernetes/Helm");
+        assert_eq!(resolve_tag(&f.tag_aliases, "k8sx"), "k8sx");
+
+        assert_eq!(tag_counts(&f)[0], ("kubernetes".to_string(), 3));
+        assert!(matches!(
+            define_tag_alias(&mut f, "kubernetes", "kube"),
+            Err(Error::Validation(_))
+        ));
+
+        assert_eq!(
+            normalize_feed(&mut f, &TagPolicy::default()),
+            vec!["1", "2"]
+        );
+        assert_eq!(f.links[1].tags, vec!["kubernetes", "kubernetes/helm"]);
+
+        assert_eq!(
+            remove_tag_alias(&mut f, "KUBE").as_deref(),
+            Some("kubernetes")
+        );
+        assert_eq!(delete_tag(&mut f, "k8s"), vec!["0", "1", "2"]);
+
+        // The new name is normalized and alias-resolved.
+        let mut f = feed(&[&["Ops"], &["infra"]]);
+        define_tag_alias(&mut f, "k8s", "kubernetes").unwrap();
+        assert_eq!(rename_tag(&mut f, "ops", " Dev  Ops "), vec!["0"]);
+        assert_eq!(f.links[0].tags, vec!["dev ops"]);
+        merge_tags(&mut f, &["dev ops", "infra"], "K8S");
+        assert_eq!(f.links[0].tags, vec!["kubernetes"]);
+        assert_eq!(f.links[1].tags, vec!["kubernetes"]);
+    }
+}
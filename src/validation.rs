@@ -3,8 +3,15 @@
 //! The parsers here return `Result<_, String>` so they plug directly into
 //! `clap`'s `value_parser` attribute. The error strings are short, user-facing
 //! messages suitable for terminal output.
+//!
+//! [`validate_link`] checks a whole [`Link`] against [`LinkRules`]; [`add`](crate::add)
+//! runs it before inserting or updating a link.
 
-use anyhow::Result;
+use crate::canonical::{UrlParts, split_url};
+use crate::datetime;
+use crate::error::{Error, Result as CrateResult};
+use crate::linkleaf_proto::{DateTime, Link};
+use crate::tags::SEPARATOR;
 use time::{Date, format_description::FormatItem, macros::format_description};
 
 // A shared, zero-allocation format description for strict `YYYY-MM-DD`.
@@ -42,43 +49,320 @@ pub fn parse_date(s: &str) -> Result<Date, String> {
     Date::parse(s.trim(), DATE_FMT).map_err(|e| e.to_string())
 }
 
+/// Parse a datetime for CLI arguments; see [`DateTime::parse`] for the
+/// accepted forms.
+///
+/// ## Behavior
+/// - Trims surrounding whitespace.
+/// - Accepts `YYYY-MM-DD HH:MM:SS`, ISO-8601 / RFC 3339
+///   (`2025-09-02T12:34:56+02:00`) and RFC 2822.
+/// - A bare `YYYY-MM-DD` is midnight; without an offset the result has none.
+///
+/// ## Returns
+/// - `Ok(DateTime)` on success.
+/// - `Err(String)` with a short, user-friendly message otherwise.
+///
+/// ## Examples
+/// ```
+/// use linkleaf_core::validation::parse_datetime;
+/// let dt = parse_datetime("2025-09-02 12:34:56").unwrap();
+/// assert_eq!(format!("{dt:#}"), "2025-09-02 12:34:56");
+/// assert!(parse_datetime("2025-09-02T12:34:56Z").is_ok());
+/// assert!(parse_datetime("2025-02-30 00:00:00").is_err());
+/// ```
+pub fn parse_datetime(s: &str) -> Result<DateTime, String> {
+    datetime::parse_datetime(s)
+}
+
 /// Parse a comma-separated tag list into a vector of tags.
 ///
 /// ## Behavior
 /// - Splits on commas (`,`).
-/// - Trims whitespace around each tag.
+/// - Trims whitespace around each tag, and around each level of a
+///   hierarchical tag (`"lang / rust"` → `"lang/rust"`).
 /// - Drops empty entries (e.g., consecutive commas or trailing commas).
 /// - **Preserves** original case and **preserves order**; no de-duplication.
 ///   (Use a normalization step elsewhere if you need lowercase/unique tags.)
 ///
 /// ## Arguments
-/// - `raw`: A string like `"rust, async , tokio"`.
+/// - `raw`: A string like `"rust, async , lang/go"`.
 ///
 /// ## Returns
 /// - `Ok(Vec<String>)` with the parsed tags (possibly empty).
-/// - `Err(String)` is not used currently; the function is effectively infallible,
-///   but the `Result` type makes it convenient to use with `clap`.
+/// - `Err(String)` if a hierarchical tag has an empty level (`"lang/"`,
+///   `"/rust"`, `"lang//rust"`).
 ///
 /// ## Examples
 /// ```
 /// use linkleaf_core::validation::parse_tags;
 /// assert_eq!(parse_tags(" a, b ,  ,c ").unwrap(), vec!["a","b","c"]);
 /// assert!(parse_tags(" , , ").unwrap().is_empty());
+/// assert_eq!(parse_tags("lang / rust").unwrap(), vec!["lang/rust"]);
+/// assert!(parse_tags("lang//rust").is_err());
 /// ```
 pub fn parse_tags(raw: &str) -> Result<Vec<String>, String> {
-    let tags = raw
-        .split(',')
+    raw.split(',')
         .map(|t| t.trim())
         .filter(|t| !t.is_empty())
-        .map(|t| t.to_string())
-        .collect();
+        .map(|t| {
+            check_tag_path(t)?;
+            Ok(t.split(SEPARATOR)
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(&SEPARATOR.to_string()))
+        })
+        .collect()
+}
+
+/// Parse and check a URL with the default [`LinkRules`] (`http`/`https`
+/// only, at most 2048 characters).
+///
+/// ## Behavior
+/// - Trims surrounding whitespace.
+/// - Requires `scheme://host` with an allowed scheme, a non-empty host made of
+///   letters, digits, `-` and `.` (or a bracketed IPv6 literal), and an
+///   optional numeric port.
+/// - Rejects whitespace and control characters anywhere in the URL.
+///
+/// ## Returns
+/// - `Ok(String)` with the trimmed URL.
+/// - `Err(String)` with a short, user-friendly message otherwise.
+///
+/// ## Examples
+/// ```
+/// use linkleaf_core::validation::parse_url;
+/// assert_eq!(parse_url(" https://tokio.rs/ ").unwrap(), "https://tokio.rs/");
+/// assert!(parse_url("tokio.rs").is_err());
+/// assert!(parse_url("ftp://example.com/file").is_err());
+/// ```
+pub fn parse_url(s: &str) -> Result<String, String> {
+    let url = s.trim();
+    check_url(url, &LinkRules::default())?;
+    Ok(url.to_string())
+}
+
+/// How strictly [`validate_link`] checks a link.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Accept everything.
+    Off,
+    /// Only require a non-empty title and URL (the fields `feed.proto` marks
+    /// as required).
+    Required,
+    /// Apply every rule of [`LinkRules`].
+    #[default]
+    Strict,
+}
+
+/// Rules for [`validate_link`]. Lengths are counted in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRules {
+    /// Defaults to [`Strictness::Strict`], so that [`validate_link`] and
+    /// [`parse_url`] check new input fully.
+    /// [`AddOptions::validation`](crate::AddOptions::validation) and
+    /// [`LinkPatch::validation`](crate::LinkPatch#structfield.validation)
+    /// default to [`Strictness::Required`] instead: they also see links that
+    /// are already in a feed, and older feeds store e.g. a plain name as `via`.
+    pub strictness: Strictness,
+    /// Accepted URL schemes for `url` and `via`, lowercase. Defaults to
+    /// `http` and `https`.
+    pub allowed_schemes: Vec<String>,
+    /// Defaults to 512.
+    pub max_title_len: usize,
+    /// Applies to `url` and `via`. Defaults to 2048.
+    pub max_url_len: usize,
+    /// Defaults to 4096.
+    pub max_summary_len: usize,
+    /// Defaults to 64.
+    pub max_tag_len: usize,
+    /// Defaults to 32.
+    pub max_tags: usize,
+}
+
+impl Default for LinkRules {
+    fn default() -> Self {
+        LinkRules {
+            strictness: Strictness::Strict,
+            allowed_schemes: vec!["http".into(), "https".into()],
+            max_title_len: 512,
+            max_url_len: 2048,
+            max_summary_len: 4096,
+            max_tag_len: 64,
+            max_tags: 32,
+        }
+    }
+}
+
+/// Check `link` against `rules`.
+///
+/// ## Behavior
+/// With [`Strictness::Strict`]:
+/// - `title` must not be blank and `url` must be a valid URL (see
+///   [`parse_url`]) with an allowed scheme.
+/// - `via`, if present and non-empty, must be a valid URL too.
+/// - Title, URL, summary and tags must respect the maximum lengths.
+/// - Tags must not be empty, start or end with whitespace, contain commas
+///   or control characters, or have an empty hierarchy level (`lang//rust`).
+///
+/// ## Errors
+/// [`Error::Validation`] listing every violated rule, separated by `; `.
+///
+/// ## Example
+/// ```
+/// use linkleaf_core::linkleaf_proto::Link;
+/// use linkleaf_core::validation::{LinkRules, validate_link};
+///
+/// let link = Link { title: "".into(), url: "tokio.rs".into(), ..Default::default() };
+/// let err = validate_link(&link, &LinkRules::default()).unwrap_err();
+/// assert!(err.to_string().contains("title must not be empty"));
+/// ```
+pub fn validate_link(link: &Link, rules: &LinkRules) -> CrateResult<()> {
+    let mut problems = Vec::new();
+    match rules.strictness {
+        Strictness::Off => {}
+        Strictness::Required => {
+            if link.title.trim().is_empty() {
+                problems.push("title must not be empty".to_string());
+            }
+            if link.url.trim().is_empty() {
+                problems.push("url must not be empty".to_string());
+            }
+        }
+        Strictness::Strict => {
+            if link.title.trim().is_empty() {
+                problems.push("title must not be empty".to_string());
+            } else if link.title.chars().count() > rules.max_title_len {
+                problems.push(format!(
+                    "title is longer than {} characters",
+                    rules.max_title_len
+                ));
+            }
+            if let Err(e) = check_url(&link.url, rules) {
+                problems.push(format!("url: {e}"));
+            }
+            if let Some(via) = link.via.as_ref().filter(|v| !v.url.is_empty())
+                && let Err(e) = check_url(&via.url, rules)
+            {
+                problems.push(format!("via: {e}"));
+            }
+            if let Some(summary) = &link.summary
+                && summary.content.chars().count() > rules.max_summary_len
+            {
+                problems.push(format!(
+                    "summary is longer than {} characters",
+                    rules.max_summary_len
+                ));
+            }
+            if link.tags.len() > rules.max_tags {
+                problems.push(format!("more than {} tags", rules.max_tags));
+            }
+            for tag in &link.tags {
+                if let Err(e) = check_tag(tag, rules) {
+                    problems.push(format!("tag {tag:?}: {e}"));
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Validation(problems.join("; ")))
+    }
+}
 
-    Ok(tags)
+fn check_url(url: &str, rules: &LinkRules) -> Result<(), String> {
+    if url.is_empty() {
+        return Err("must not be empty".into());
+    }
+    if url.chars().count() > rules.max_url_len {
+        return Err(format!("longer than {} characters", rules.max_url_len));
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("must not contain whitespace".into());
+    }
+    let Some(UrlParts {
+        scheme, host, port, ..
+    }) = split_url(url)
+    else {
+        return Err("missing scheme (expected e.g. https://)".into());
+    };
+    let valid_scheme = scheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return Err(format!("invalid scheme {scheme:?}"));
+    }
+    if !rules
+        .allowed_schemes
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(scheme))
+    {
+        return Err(format!("scheme {scheme:?} is not allowed"));
+    }
+
+    let (host, v6) = match host.strip_prefix('[') {
+        Some(v6) => match v6.strip_suffix(']') {
+            Some(v6) => (v6, true),
+            None => return Err("unterminated IPv6 address".into()),
+        },
+        None => (host, false),
+    };
+    if host.is_empty() {
+        return Err("missing host".into());
+    }
+    let host_ok = if v6 {
+        host.chars()
+            .all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.')
+    } else {
+        host.chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+    };
+    if !host_ok {
+        return Err(format!("invalid host {host:?}"));
+    }
+    if let Some(port) = port
+        && port.parse::<u16>().is_err()
+    {
+        return Err(format!("invalid port {port:?}"));
+    }
+    Ok(())
+}
+
+fn check_tag(tag: &str, rules: &LinkRules) -> Result<(), String> {
+    if tag.trim().is_empty() {
+        return Err("must not be empty".into());
+    }
+    if tag.trim() != tag {
+        return Err("must not start or end with whitespace".into());
+    }
+    if tag.chars().count() > rules.max_tag_len {
+        return Err(format!("longer than {} characters", rules.max_tag_len));
+    }
+    if tag.contains(',') || tag.chars().any(char::is_control) {
+        return Err("must not contain commas or control characters".into());
+    }
+    check_tag_path(tag).map_err(|_| format!("has an empty level between `{SEPARATOR}`s"))
+}
+
+/// Reject hierarchical tags with an empty level.
+fn check_tag_path(tag: &str) -> Result<(), String> {
+    if tag.split(SEPARATOR).any(|level| level.trim().is_empty()) {
+        return Err(format!(
+            "malformed tag `{tag}`: empty level between `{SEPARATOR}`s"
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::linkleaf_proto::{Summary, Via};
     use time::Date;
 
     // ---------- parse_date ----------
@@ -107,6 +391,30 @@ mod tests {
         assert!(parse_date("2025-09-02 12:34:56").is_err());
     }
 
+    #[test]
+    fn parse_datetime_accepts_documented_forms() {
+        let dt = parse_datetime(" 2025-09-02 12:34:56 ").unwrap();
+        assert_eq!((dt.year, dt.month, dt.day, dt.seconds), (2025, 9, 2, 56));
+        assert_eq!(dt.utc_offset, None);
+        let dt = parse_datetime("Tue, 2 Sep 2025 12:34:56 +0200").unwrap();
+        assert_eq!(dt.utc_offset, Some(7200));
+        assert_eq!(parse_datetime("2025-09-02 12:34").unwrap().seconds, 0);
+        assert!(parse_datetime("2025-09-02 12:34:").is_err());
+        assert!(parse_datetime("yesterday").is_err());
+    }
+
+    #[test]
+    fn parse_datetime_accepts_leap_seconds_only_at_the_end_of_a_utc_day() {
+        assert_eq!(parse_datetime("2016-12-31T23:59:60Z").unwrap().seconds, 60);
+        assert!(parse_datetime("2017-01-01T00:59:60+01:00").is_ok());
+        assert!(parse_datetime("Sat, 31 Dec 2016 18:59:60 -0500").is_ok());
+        assert!(parse_datetime("2016-12-31 23:59:60").is_ok(), "read as UTC");
+
+        assert!(parse_datetime("2025-03-04T10:11:60Z").is_err());
+        assert!(parse_datetime("2016-12-31T23:59:60+01:00").is_err());
+        assert!(parse_datetime("Tue, 4 Mar 2025 10:11:60 +0000").is_err());
+    }
+
     #[test]
     fn parse_date_rejects_wrong_separator_or_format() {
         assert!(parse_date("2025/09/02").is_err());
@@ -160,4 +468,79 @@ mod tests {
         let tags = parse_tags(" , ,  , ").expect("ok");
         assert!(tags.is_empty());
     }
+
+    #[test]
+    fn parse_tags_trims_levels_and_rejects_empty_ones() {
+        let tags = parse_tags("lang / rust, web").expect("ok");
+        assert_eq!(tags, vec!["lang/rust", "web"]);
+        for raw in ["lang/", "/rust", "lang//rust", "a, lang/ /rust"] {
+            let err = parse_tags(raw).unwrap_err();
+            assert!(err.contains("empty level"), "{raw}: {err}");
+        }
+    }
+
+    // ---------- parse_url / validate_link ----------
+
+    #[test]
+    fn parse_url_accepts_common_urls() {
+        for url in [
+            "https://tokio.rs",
+            "http://localhost:8080/a?b=c#d",
+            "https://user@例え.jp/",
+            "https://[::1]:443/",
+        ] {
+            assert_eq!(parse_url(url).as_deref(), Ok(url), "{url}");
+        }
+    }
+
+    #[test]
+    fn parse_url_rejects_malformed_urls() {
+        for url in [
+            "",
+            "tokio.rs",
+            "javascript://alert(1)",
+            "https://",
+            "https://exa mple.com",
+            "https://example.com:99999/",
+            "https://ex_ample.com/",
+        ] {
+            assert!(parse_url(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn validate_link_reports_every_problem() {
+        let link = Link {
+            title: " ".into(),
+            url: "https://ok.example/".into(),
+            via: Some(Via::new("nope")),
+            summary: Some(Summary::new(&"x".repeat(5000))),
+            tags: vec!["rust".into(), "a,b".into(), " pad".into()],
+            ..Default::default()
+        };
+        let err = validate_link(&link, &LinkRules::default()).unwrap_err();
+        let msg = err.to_string();
+        for part in ["title", "via", "summary", "\"a,b\"", "\" pad\""] {
+            assert!(msg.contains(part), "{part} missing in {msg}");
+        }
+        assert!(!msg.contains("url:"), "url is fine: {msg}");
+    }
+
+    #[test]
+    fn strictness_levels() {
+        let link = Link {
+            title: "T".into(),
+            url: "not a url".into(),
+            ..Default::default()
+        };
+        let rules = |strictness| LinkRules {
+            strictness,
+            ..Default::default()
+        };
+        assert!(validate_link(&link, &rules(Strictness::Strict)).is_err());
+        assert!(validate_link(&link, &rules(Strictness::Required)).is_ok());
+        let empty = Link::default();
+        assert!(validate_link(&empty, &rules(Strictness::Required)).is_err());
+        assert!(validate_link(&empty, &rules(Strictness::Off)).is_ok());
+    }
 }
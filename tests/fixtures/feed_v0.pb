@@ -0,0 +1,3 @@
+
+LegacyM
+$9a3d5e21-7c4b-4f08-8e16-2b0d6f1a4c93Serdehttps://serde.rs/" (
\ No newline at end of file
@@ -0,0 +1,6 @@
+
+My Links
+$5f0c7c3e-2b1a-4d7e-9a51-0c1f3b9d2e10Tokio - Asynchronous Rusthttps://tokio.rs/" 	(05*#
+!A runtime for reliable async apps2rust2async:
+https://news.ycombinator.com/g
+$0b6f2a44-8d3e-4f19-b2c7-6e5a1d9c3f87The Rust Bookhttps://doc.rust-lang.org/book/"	 2rust 
\ No newline at end of file